@@ -14,6 +14,8 @@ pub struct Bar {
     weight: Mass,
     gauge: Length,
     kind: BarKind,
+    #[serde(default)]
+    capacity: Option<Mass>,
 }
 
 impl Bar {
@@ -23,6 +25,16 @@ impl Bar {
             weight,
             gauge,
             kind,
+            capacity: None,
+        }
+    }
+
+    /// The same bar, rated to carry at most `capacity` of plates and adapters.
+    #[must_use]
+    pub fn with_capacity(self, capacity: Mass) -> Self {
+        Bar {
+            capacity: Some(capacity),
+            ..self
         }
     }
 
@@ -40,6 +52,18 @@ impl Bar {
     pub fn kind(&self) -> &BarKind {
         &self.kind
     }
+
+    #[must_use]
+    pub fn capacity(&self) -> Option<Mass> {
+        self.capacity
+    }
+
+    /// Whether the bar is rated to be loaded to a total of `weight`.
+    #[must_use]
+    pub fn holds(&self, weight: Mass) -> bool {
+        self.capacity
+            .is_none_or(|capacity| weight - self.weight <= capacity)
+    }
 }
 
 impl Display for Bar {
@@ -50,6 +74,40 @@ impl Display for Bar {
             self.kind,
             self.gauge.get::<centimeter>(),
             self.weight.get::<kilogram>(),
-        )
+        )?;
+
+        if let Some(capacity) = self.capacity {
+            write!(f, " rated {}kg", capacity.get::<kilogram>())?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use crate::test_support::{cm, kg, olympic_plates};
+    use crate::{Bar, BarKind, Gym, GymError, Requirement};
+
+    #[test]
+    fn requirement_beyond_bar_rating_reports_over_capacity() {
+        let bar = Bar::new(kg(10), cm(5), BarKind::Barbell).with_capacity(kg(10));
+
+        let plates = olympic_plates(5, 4);
+        let gym = Gym::new(&plates, &[bar]);
+        let changes = |weight: &str| gym.changes(&[Requirement::from_str(weight).unwrap()]);
+
+        assert_eq!(
+            Gym::new(&plates, &[bar]).weights().bar(bar),
+            vec![kg(10), kg(20)]
+        );
+        assert!(changes("20b").is_ok());
+        assert!(matches!(changes("30b"), Err(GymError::OverCapacity(_))));
+        assert!(matches!(
+            changes("40b"),
+            Err(GymError::ImpossibleRequirement(_))
+        ));
     }
 }
@@ -12,14 +12,19 @@ use crate::GymError;
 pub enum BarKind {
     Dumbbell,
     Barbell,
+    Kettlebell,
+    Machine,
 }
 
 impl BarKind {
+    /// How many identically loaded implements a requirement of this kind asks for unless it
+    /// says otherwise: a matched pair of dumbbells, or a single one of anything else. Each bar
+    /// listed in an inventory provides this many, so a dumbbell handle stands for a pair.
     #[must_use]
-    pub(crate) fn required_similar_plates(self) -> usize {
+    pub(crate) fn default_quantity(self) -> usize {
         match self {
-            BarKind::Dumbbell => 4,
-            BarKind::Barbell => 2,
+            BarKind::Dumbbell => 2,
+            BarKind::Barbell | BarKind::Kettlebell | BarKind::Machine => 1,
         }
     }
 }
@@ -31,6 +36,8 @@ impl FromStr for BarKind {
         match s {
             "d" | "db" | "dumbbell" => Ok(BarKind::Dumbbell),
             "b" | "bb" | "barbell" => Ok(BarKind::Barbell),
+            "k" | "kb" | "kettlebell" => Ok(BarKind::Kettlebell),
+            "m" | "machine" => Ok(BarKind::Machine),
             _ => Err(GymError::InvalidBarKind(s.to_string())),
         }
     }
@@ -0,0 +1,136 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt::Display,
+};
+
+use serde::{Deserialize, Serialize};
+use uom::si::rational64::{Length, Mass};
+
+use crate::{Bar, format};
+
+/// A sleeve adapter which fits over a bar's sleeve and takes plates of another gauge.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct Adapter {
+    bar_gauge: Length,
+    sleeve_gauge: Length,
+    weight: Mass,
+}
+
+impl Adapter {
+    #[must_use]
+    pub fn new(bar_gauge: Length, sleeve_gauge: Length, weight: Mass) -> Self {
+        Adapter {
+            bar_gauge,
+            sleeve_gauge,
+            weight,
+        }
+    }
+
+    /// The gauge of the bars the adapter fits over.
+    #[must_use]
+    pub fn bar_gauge(&self) -> Length {
+        self.bar_gauge
+    }
+
+    /// The gauge the adapter presents to plates.
+    #[must_use]
+    pub fn sleeve_gauge(&self) -> Length {
+        self.sleeve_gauge
+    }
+
+    #[must_use]
+    pub fn weight(&self) -> Mass {
+        self.weight
+    }
+}
+
+impl Display for Adapter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}-{}cm adapter {}kg",
+            format::length_to_dec_string(self.bar_gauge),
+            format::length_to_dec_string(self.sleeve_gauge),
+            format::mass_to_dec_string(self.weight)
+        )
+    }
+}
+
+/// Which plate gauges fit which sleeve gauges, and the adapters available. Plates always fit
+/// a sleeve of their own gauge.
+#[derive(Clone, Debug, Default)]
+pub struct Compatibility {
+    fits: BTreeSet<(Length, Length)>,
+    adapters: BTreeMap<Adapter, usize>,
+}
+
+impl Compatibility {
+    /// Lets plates of `plate_gauge` go on sleeves of `sleeve_gauge`.
+    pub fn add_fit(&mut self, plate_gauge: Length, sleeve_gauge: Length) {
+        self.fits.insert((plate_gauge, sleeve_gauge));
+    }
+
+    pub fn add_adapter(&mut self, adapter: Adapter, count: usize) {
+        *self.adapters.entry(adapter).or_default() += count;
+    }
+
+    #[must_use]
+    pub fn fits(&self, plate_gauge: Length, sleeve_gauge: Length) -> bool {
+        plate_gauge == sleeve_gauge || self.fits.contains(&(plate_gauge, sleeve_gauge))
+    }
+
+    /// The adapters there are enough of to fit both sleeves of `quantity` bars like `bar`.
+    #[must_use]
+    pub fn adapters(&self, bar: &Bar, quantity: usize) -> Vec<Adapter> {
+        self.adapters
+            .iter()
+            .filter(|(adapter, count)| {
+                adapter.bar_gauge() == bar.gauge() && **count >= quantity.saturating_mul(2)
+            })
+            .map(|(adapter, _)| *adapter)
+            .collect()
+    }
+
+    /// Every adapter, whether or not it fits a bar, with how many there are.
+    pub(crate) fn all_adapters(&self) -> impl Iterator<Item = (&Adapter, &usize)> {
+        self.adapters.iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use crate::test_support::{cm, kg};
+    use crate::{Adapter, Bar, BarKind, Gym, Inventory, Plate, Requirement};
+
+    #[test]
+    fn workout_fits_adapters_for_other_gauge_plates() {
+        let standard = cm((5, 2));
+        let olympic = cm(5);
+
+        let bar = Bar::new(kg(10), standard, BarKind::Barbell);
+        let adapter = Adapter::new(standard, olympic, kg(1));
+
+        let mut plates = vec![Plate::new(kg(5), standard); 2];
+        plates.extend(vec![Plate::new(kg(20), olympic); 2]);
+
+        let requirements = vec![
+            Requirement::from_str("20b").unwrap(),
+            Requirement::from_str("52b").unwrap(),
+        ];
+
+        let without_adapters = Gym::new(&plates, &[bar]);
+        assert!(without_adapters.changes(&requirements).is_err());
+
+        let mut inventory = Inventory::new(plates, vec![bar]);
+        inventory.add_adapter(adapter, 2);
+        let gym = Gym::from_inventory(&inventory);
+
+        let workout = gym.workout(&requirements).expect("workout should succeed");
+
+        assert_eq!(gym.changes(&requirements).unwrap(), 3);
+        assert_eq!(workout.get(bar)[0].adapter(), None);
+        assert_eq!(workout.get(bar)[1].adapter(), Some(&adapter));
+    }
+}
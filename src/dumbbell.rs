@@ -1,64 +1,189 @@
 use std::{fmt::Display, hash::Hash};
 
 use itertools::Itertools;
-use uom::si::rational64::Mass;
+use uom::{
+    num_rational::Rational64,
+    num_traits::{CheckedAdd, CheckedMul},
+    si::{mass::kilogram, rational64::Mass},
+};
 
-use crate::{Bar, Plate, format};
+use crate::{Adapter, Bar, GymError, MassScale, Plate, format};
 
 #[derive(Clone, Debug, PartialEq, Eq, Hash)]
 
 pub struct Dumbbell {
     plates: Vec<Plate>,
     bar: Bar,
+    adapter: Option<Adapter>,
+    odd_plate: Option<Plate>,
     weight: Mass,
 }
 
 impl Dumbbell {
+    ///
+    /// # Panics
+    /// If the bar plus two sides of `plates` is too heavy to represent.
+    ///
     #[must_use]
     pub fn new(plates: Vec<Plate>, bar: Bar) -> Self {
-        let plates_weight = plates.iter().map(|plate| plate.weight()).sum();
+        match Self::try_new(plates, bar) {
+            Ok(dumbbell) => dumbbell,
+            Err(error) => panic!("{error}"),
+        }
+    }
+
+    ///
+    /// # Errors
+    /// If summing the plates or adding them to the bar overflows.
+    ///
+    pub fn try_new(plates: Vec<Plate>, bar: Bar) -> Result<Self, GymError> {
+        let plates_weight = plates
+            .iter()
+            .try_fold(Rational64::default(), |acc, plate| {
+                acc.checked_add(&plate.weight().get::<kilogram>())
+                    .ok_or(GymError::PlateOverflow(*plate))
+            })?;
 
+        let weight = plates_weight
+            .checked_mul(&Rational64::from_integer(2))
+            .and_then(|weight| weight.checked_add(&bar.weight().get::<kilogram>()))
+            .ok_or(GymError::BarOverflow(bar))?;
+
+        Ok(Dumbbell {
+            plates,
+            bar,
+            adapter: None,
+            odd_plate: None,
+            weight: Mass::new::<kilogram>(weight),
+        })
+    }
+
+    /// A dumbbell with a known total. The gym sums loadings in integer units as it enumerates
+    /// them, so `weight` counts the bar, both sides of `plates` and both adapters.
+    #[must_use]
+    pub(crate) fn with_weight(
+        plates: Vec<Plate>,
+        bar: Bar,
+        adapter: Option<Adapter>,
+        weight: Mass,
+    ) -> Self {
         Dumbbell {
             plates,
             bar,
-            weight: bar.weight() + plates_weight + plates_weight,
+            adapter,
+            odd_plate: None,
+            weight,
+        }
+    }
+
+    /// The same dumbbell with `plate` on the outside of one sleeve only. The weight is kept
+    /// as it is, so it should already include the plate.
+    #[must_use]
+    pub(crate) fn with_odd_plate(self, plate: Plate) -> Self {
+        Dumbbell {
+            odd_plate: Some(plate),
+            ..self
         }
     }
 
+    /// The plates on both sleeves, heaviest first.
     #[must_use]
     pub fn plates(&self) -> &[Plate] {
         &self.plates
     }
 
+    /// The microloading plate on one sleeve only, if the sides differ.
     #[must_use]
-    pub fn bar(&self) -> &Bar {
-        &self.bar
+    pub fn odd_plate(&self) -> Option<&Plate> {
+        self.odd_plate.as_ref()
     }
 
+    /// The plates on each sleeve, heaviest first, with any odd plate on the first.
     #[must_use]
-    pub fn weight(&self) -> &Mass {
-        &self.weight
+    pub fn sides(&self) -> [Vec<Plate>; 2] {
+        let mut first = self.plates.clone();
+        first.extend(self.odd_plate);
+        [first, self.plates.clone()]
     }
 
     #[must_use]
-    pub(crate) fn adjacent(&self, other: &Self) -> bool {
-        if self.bar != other.bar {
-            return false;
-        }
+    pub fn bar(&self) -> &Bar {
+        &self.bar
+    }
 
-        if self.plates.len().abs_diff(other.plates.len()) != 1 {
-            return false;
+    /// The true total, from each plate's actual weight. The same as [`Dumbbell::weight`] unless
+    /// some plates are measurably off their nominal weight and the gym plans with nominal
+    /// weights.
+    ///
+    /// # Errors
+    /// If the measured weights can't be summed without overflowing.
+    ///
+    pub fn actual_weight(&self) -> Result<Mass, GymError> {
+        let mut scale = MassScale::default()
+            .including(self.bar.weight())
+            .ok_or(GymError::BarOverflow(self.bar))?;
+        if let Some(adapter) = self.adapter {
+            scale = scale
+                .including(adapter.weight())
+                .ok_or(GymError::AdapterOverflow(adapter))?;
+        }
+        for plate in self.plates.iter().chain(&self.odd_plate) {
+            scale = scale
+                .including(plate.actual_weight())
+                .ok_or(GymError::PlateOverflow(*plate))?;
         }
 
-        let longer = if self.plates.len() > other.plates.len() {
-            self
-        } else {
-            other
-        };
+        let sleeve = self
+            .plates
+            .iter()
+            .map(|plate| (plate.actual_weight(), GymError::PlateOverflow(*plate)))
+            .chain(
+                self.adapter
+                    .map(|adapter| (adapter.weight(), GymError::AdapterOverflow(adapter))),
+            )
+            .try_fold(0_i64, |acc, (mass, error)| {
+                scale
+                    .units(mass)
+                    .and_then(|units| acc.checked_add(units))
+                    .ok_or(error)
+            })?;
+        let odd_plate = self.odd_plate.map_or(Ok(0), |plate| {
+            scale
+                .units(plate.actual_weight())
+                .ok_or(GymError::PlateOverflow(plate))
+        })?;
 
-        let last_plate = longer.plates.last().unwrap();
+        let total = scale
+            .units(self.bar.weight())
+            .and_then(|bar| {
+                sleeve
+                    .checked_mul(2)?
+                    .checked_add(bar)?
+                    .checked_add(odd_plate)
+            })
+            .ok_or(GymError::BarOverflow(self.bar))?;
 
-        (*self.weight() - *other.weight()).abs() == last_plate.weight() + last_plate.weight()
+        Ok(scale.mass(total))
+    }
+
+    /// Whether a full-size plate sets the bar at standard height off the floor.
+    #[must_use]
+    pub fn at_standard_height(&self) -> bool {
+        self.plates
+            .iter()
+            .chain(&self.odd_plate)
+            .any(|plate| plate.is_full_size())
+    }
+
+    /// The adapter fitted to each sleeve, if the plates need one.
+    #[must_use]
+    pub fn adapter(&self) -> Option<&Adapter> {
+        self.adapter.as_ref()
+    }
+
+    #[must_use]
+    pub fn weight(&self) -> &Mass {
+        &self.weight
     }
 }
 
@@ -76,18 +201,71 @@ impl Ord for Dumbbell {
 
 impl Display for Dumbbell {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let plates = self
-            .plates
-            .iter()
-            .map(|p| p.weight())
-            .map(format::mass_to_dec_string)
-            .join(", ");
+        let format_plates = |plates: &[Plate]| {
+            plates
+                .iter()
+                .map(|p| p.weight())
+                .map(format::mass_to_dec_string)
+                .join(", ")
+        };
 
-        write!(
-            f,
-            "[{}] ({}kg)",
-            plates,
-            format::mass_to_dec_string(*self.weight())
-        )
+        if self.odd_plate.is_some() {
+            let [first, second] = self.sides();
+            write!(
+                f,
+                "[{}] / [{}]",
+                format_plates(&first),
+                format_plates(&second)
+            )?;
+        } else {
+            write!(f, "[{}]", format_plates(&self.plates))?;
+        }
+        if let Some(adapter) = &self.adapter {
+            write!(f, " on {adapter}")?;
+        }
+
+        write!(f, " ({}kg", format::mass_to_dec_string(*self.weight()))?;
+        if let Ok(actual) = self.actual_weight()
+            && actual != self.weight
+        {
+            write!(f, ", actual {}kg", format::mass_to_dec_string(actual))?;
+        }
+
+        write!(f, ")")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use crate::test_support::{cm, kg};
+    use crate::{Bar, BarKind, Gym, Inventory, Plate, Requirement};
+
+    #[test]
+    fn microloading_puts_one_small_plate_on_one_side() {
+        let bar = Bar::new(kg(20), cm(5), BarKind::Barbell);
+        let small = Plate::new(kg((5, 4)), cm(5));
+
+        let mut plates = vec![Plate::new(kg(20), cm(5)); 2];
+        plates.push(small);
+        let mut inventory = Inventory::new(plates, vec![bar]);
+        let requirements = ["60b", "61.25b"].map(|req| Requirement::from_str(req).unwrap());
+
+        assert!(
+            Gym::from_inventory(&inventory)
+                .changes(&requirements)
+                .is_err()
+        );
+
+        inventory.allow_microloading(kg((5, 4)));
+        let gym = Gym::from_inventory(&inventory);
+        assert_eq!(gym.changes(&requirements).unwrap(), 1);
+
+        let workout = gym.workout(&requirements).expect("workout should succeed");
+        let dumbbells = workout.get(bar);
+        assert_eq!(dumbbells[0].to_string(), "[20] (60kg)");
+        assert_eq!(dumbbells[1].to_string(), "[20, 1.25] / [20] (61.25kg)");
+        assert_eq!(dumbbells[1].odd_plate(), Some(&small));
     }
 }
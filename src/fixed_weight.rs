@@ -0,0 +1,124 @@
+use std::fmt::Display;
+
+use serde::{Deserialize, Serialize};
+use uom::si::rational64::Mass;
+
+use crate::{BarKind, format};
+
+/// A fixed dumbbell, kettlebell or barbell that is picked up as is, with no plates to change.
+/// Like a bar, one listing stands for the kind's usual quantity, so a fixed dumbbell is a pair.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct FixedWeight {
+    weight: Mass,
+    kind: BarKind,
+    /// How many identical implements there are, if not the kind's usual quantity.
+    #[serde(default)]
+    count: Option<usize>,
+}
+
+impl FixedWeight {
+    /// The kind's usual quantity of the implement: a pair of dumbbells, or a single kettlebell
+    /// or barbell.
+    #[must_use]
+    pub fn new(weight: Mass, kind: BarKind) -> Self {
+        FixedWeight {
+            weight,
+            kind,
+            count: None,
+        }
+    }
+
+    /// The same fixed weight, with `count` identical implements.
+    #[must_use]
+    pub fn with_count(self, count: usize) -> Self {
+        FixedWeight {
+            count: Some(count).filter(|count| *count != self.kind.default_quantity()),
+            ..self
+        }
+    }
+
+    /// How many identical implements there are.
+    #[must_use]
+    pub fn count(&self) -> usize {
+        self.count.unwrap_or(self.kind.default_quantity())
+    }
+
+    #[must_use]
+    pub fn weight(&self) -> Mass {
+        self.weight
+    }
+
+    #[must_use]
+    pub fn kind(&self) -> &BarKind {
+        &self.kind
+    }
+}
+
+impl Display for FixedWeight {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Some(count) = self.count {
+            write!(f, "{count}x ")?;
+        }
+
+        write!(
+            f,
+            "Fixed {} {}kg",
+            self.kind,
+            format::mass_to_dec_string(self.weight)
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use crate::test_support::{cm, fixture_plates, kg};
+    use crate::{Bar, BarKind, FixedWeight, Gym, Implement, Inventory, Requirement};
+
+    #[test]
+    fn workout_uses_fixed_weights_without_plate_changes() {
+        let bar = Bar::new(kg(15), cm(5), BarKind::Barbell);
+        let kettlebell = FixedWeight::new(kg(16), BarKind::Kettlebell);
+
+        let mut inventory = Inventory::new(fixture_plates(), vec![bar]);
+        inventory.add_fixed(kettlebell);
+        let gym = Gym::from_inventory(&inventory);
+
+        let requirements = vec![
+            Requirement::from_str("25b").unwrap(),
+            Requirement::from_str("16k").unwrap(),
+            Requirement::from_str("25b").unwrap(),
+        ];
+
+        let workout = gym.workout(&requirements).expect("workout should succeed");
+        let timeline = workout.timeline();
+
+        assert_eq!(gym.changes(&requirements).unwrap(), 0);
+        assert_eq!(timeline.len(), 3);
+        assert_eq!(timeline[1].implement(), &Implement::Fixed(kettlebell));
+        assert!(matches!(timeline[0].implement(), Implement::Loaded(_)));
+        assert!(
+            gym.changes(&[Requirement::from_str("20k").unwrap()])
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn fixed_dumbbells_are_counted_against_the_quantity() {
+        let bar = Bar::new(kg(15), cm(5), BarKind::Barbell);
+        let dumbbell = FixedWeight::new(kg(20), BarKind::Dumbbell);
+        let plan = |dumbbell: FixedWeight, requirement: &str| {
+            let mut inventory = Inventory::new(fixture_plates(), vec![bar]);
+            inventory.add_fixed(dumbbell);
+            Gym::from_inventory(&inventory).workout(&[Requirement::from_str(requirement).unwrap()])
+        };
+
+        assert_eq!(dumbbell, dumbbell.with_count(2));
+        assert!(plan(dumbbell, "20d").is_ok());
+        assert!(plan(dumbbell, "3x20d").is_err());
+        assert!(plan(dumbbell.with_count(1), "20d").is_err());
+        assert!(plan(dumbbell.with_count(1), "1x20d").is_ok());
+        assert!(plan(dumbbell.with_count(3), "3x20d").is_ok());
+    }
+}
@@ -1,4 +1,5 @@
 use rational_extensions::to_dec_string;
+use uom::num_rational::Rational64;
 use uom::si::{
     length::centimeter,
     mass::kilogram,
@@ -18,3 +19,10 @@ pub fn length_to_dec_string(length: Length) -> String {
         .trim_end_matches('.')
         .to_string()
 }
+
+pub fn rational_to_dec_string(value: Rational64) -> String {
+    to_dec_string(&value, 10)
+        .trim_end_matches('0')
+        .trim_end_matches('.')
+        .to_string()
+}
@@ -1,95 +1,223 @@
-use std::collections::{BTreeMap, HashMap, HashSet};
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet},
+    sync::OnceLock,
+};
 
 use itertools::Itertools;
-use petgraph::{algo, prelude::UnGraphMap};
-use uom::si::rational64::Mass;
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+
+use uom::{
+    num_traits::CheckedMul,
+    si::{
+        mass::kilogram,
+        rational64::{Length, Mass},
+    },
+};
 
 use crate::{
-    Bar, BarKind, Dumbbell, GymError, GymState, GymStateId, Plate, Requirement, Weights, Workout,
+    Adapter, Bar, BarKind, Compatibility, Dumbbell, FixedWeight, GymError, GymState, Handling,
+    Implement, Inventory, Limit, Limits, LoadingId, Machine, MachineLoading, MassScale, Plate,
+    Requirement, Selection, Selector, StateGraph, Step, Weighing, Weights, Workout,
 };
 
+/// The implement used for a requirement, and every loaded implement that meets it.
+type Planned = (Option<Implement>, Vec<Implement>);
+
+/// A gym's bars, plates, selectors, machines and fixed weights. The state graph for each bar
+/// kind and quantity of implements is built the first time a requirement needs it, so a gym
+/// can be shared between threads once constructed.
 pub struct Gym {
-    states: HashMap<BarKind, HashMap<GymStateId, GymState>>,
-    distances: HashMap<BarKind, HashMap<(GymStateId, GymStateId), u32>>,
+    graphs: HashMap<(BarKind, usize), OnceLock<Result<StateGraph, GymError>>>,
+    max_quantity: usize,
     bar_options: HashMap<BarKind, Vec<Bar>>,
     weights: Weights,
+    overloaded: HashSet<(BarKind, Mass)>,
+    scale: MassScale,
+    inventory: Inventory,
+    limits: Limits,
 }
 
-
-
 impl Gym {
+    ///
+    /// # Panics
+    /// If the inventory's weights overflow. [`Gym::try_new`] returns the overflow instead, and
+    /// also checks the gym's size against its limits.
+    ///
     #[must_use]
     pub fn new(plates: &[Plate], bars: &[Bar]) -> Self {
-        let plate_counts: BTreeMap<Plate, usize> =
-            plates.iter().fold(BTreeMap::new(), |mut acc, plate| {
-                *acc.entry(*plate).or_default() += 1;
-                acc
-            });
+        Self::from_inventory(&Inventory::new(plates.to_vec(), bars.to_vec()))
+    }
 
-        let dumbbells: BTreeMap<Bar, Vec<Dumbbell>> = bars
-            .iter()
-            .map(|bar| (*bar, Self::dumbbells(&plate_counts, bar)))
-            .collect();
+    ///
+    /// # Panics
+    /// If summing any loading of the inventory overflows.
+    ///
+    #[must_use]
+    pub fn from_inventory(inventory: &Inventory) -> Self {
+        match Self::build(inventory, Limits::default()) {
+            Ok(gym) => gym,
+            Err(error) => panic!("{error}"),
+        }
+    }
 
-        let weights = dumbbells
-            .iter()
-            .fold(
-                HashMap::<BarKind, Vec<Mass>>::new(),
-                |mut acc, (bar, dumbbells)| {
-                    let weight = dumbbells.iter().map(Dumbbell::weight).collect::<Vec<_>>();
-                    acc.entry(*bar.kind()).or_default().extend(weight);
-                    acc
-                },
-            )
-            .into();
+    ///
+    /// Builds the gym only if its model stays within `limits`.
+    ///
+    /// # Errors
+    /// If the estimated number of loadings or distance entries exceeds its limit, or
+    /// the inventory's weights overflow.
+    ///
+    pub fn try_new(plates: &[Plate], bars: &[Bar], limits: Limits) -> Result<Self, GymError> {
+        Self::try_from_inventory(&Inventory::new(plates.to_vec(), bars.to_vec()), limits)
+    }
 
-        let dumbbells: HashMap<BarKind, HashMap<Bar, Vec<Dumbbell>>> =
-            dumbbells
-                .into_iter()
-                .fold(HashMap::new(), |mut acc, (bar, dumbbells)| {
-                    acc.entry(*bar.kind()).or_default().insert(bar, dumbbells);
-                    acc
-                });
+    ///
+    /// Builds the gym from an inventory only if its model stays within `limits`.
+    ///
+    /// # Errors
+    /// If the estimated number of loadings or distance entries exceeds its limit, or
+    /// the inventory's weights overflow.
+    ///
+    pub fn try_from_inventory(inventory: &Inventory, limits: Limits) -> Result<Self, GymError> {
+        Self::check_limits(inventory, limits, None)?;
+        Self::build(inventory, limits)
+    }
 
-        let states: HashMap<BarKind, HashMap<GymStateId, GymState>> = dumbbells
-            .into_iter()
-            .map(|(kind, dumbbells)| {
-                let states = dumbbells
-                    .into_values()
-                    .multi_cartesian_product()
-                    .map(|dumbbells| {
-                        GymState::new(
-                            dumbbells
-                                .into_iter()
-                                .map(|dumbbell| (*dumbbell.bar(), dumbbell))
-                                .collect::<HashMap<_, _>>(),
-                        )
-                    })
-                    .enumerate()
-                    .map(|(i, state)| (GymStateId(i), state))
-                    .collect();
-                (kind, states)
+    /// An upper bound on the loadings of each bar, selector and machine in the graph of
+    /// `graph`'s kind and quantity of implements, or in every kind at its usual quantity.
+    fn estimated_loadings(
+        inventory: &Inventory,
+        graph: Option<(BarKind, usize)>,
+    ) -> Vec<(BarKind, usize)> {
+        let plate_counts = Self::plate_counts(inventory.plates());
+        let includes = |kind: &BarKind| graph.is_none_or(|(graph_kind, _)| graph_kind == *kind);
+        // Selectors and machines only join graphs for no more than their usual quantity.
+        let includes_fixed_loadings = |kind: &BarKind| {
+            graph.is_none_or(|(graph_kind, quantity)| {
+                graph_kind == *kind && quantity <= kind.default_quantity()
             })
-            .collect();
+        };
 
-        let graphs: HashMap<BarKind, UnGraphMap<GymStateId, u32>> = states
-            .iter()
-            .map(|(kind, states)| {
-                let graph = Self::graph(states);
-                (*kind, graph)
-            })
-            .collect();
+        let combinations = |plates: &[(Plate, usize)]| {
+            plates
+                .iter()
+                .fold(1usize, |acc, (_, count)| acc.saturating_mul(count + 1))
+        };
+        // Each combination on a sleeve, plus its variants with one odd plate on a single side.
+        let sleeve_loadings = |sleeve_gauge, quantity| {
+            let compatibility = inventory.compatibility();
+            let odd_plates = Self::odd_plates(
+                &plate_counts,
+                compatibility,
+                sleeve_gauge,
+                inventory.weighing(),
+                inventory.microloading(),
+            );
+            combinations(&Self::usable_plates(
+                &plate_counts,
+                compatibility,
+                sleeve_gauge,
+                quantity,
+            ))
+            .saturating_mul(odd_plates.len() + 1)
+        };
 
-        let distances: HashMap<BarKind, HashMap<(GymStateId, GymStateId), u32>> = graphs
-            .iter()
-            .map(|(kind, graph)| {
-                let distances = algo::johnson(&graph, |e| *e.2)
-                    .unwrap_or_default()
-                    .into_iter()
-                    .collect::<HashMap<_, _>>();
-                (*kind, distances)
+        let bars = match graph {
+            Some((_, quantity)) => Self::enough_bars(inventory.bars(), quantity),
+            None => inventory.bars().to_vec(),
+        };
+
+        bars.iter()
+            .filter(|bar| includes(bar.kind()))
+            .map(|bar| {
+                let quantity =
+                    graph.map_or(bar.kind().default_quantity(), |(_, quantity)| quantity);
+                let loadings = inventory
+                    .compatibility()
+                    .adapters(bar, quantity)
+                    .iter()
+                    .fold(
+                        sleeve_loadings(bar.gauge(), quantity),
+                        |loadings, adapter| {
+                            loadings
+                                .saturating_add(sleeve_loadings(adapter.sleeve_gauge(), quantity))
+                        },
+                    );
+                (*bar.kind(), loadings)
             })
-            .collect();
+            .chain(
+                inventory
+                    .selectors()
+                    .iter()
+                    .filter(|selector| includes_fixed_loadings(selector.kind()))
+                    .map(|selector| {
+                        let loadings = combinations(&Self::add_on_plates(&plate_counts, selector))
+                            .saturating_mul(selector.settings().len());
+                        (*selector.kind(), loadings)
+                    }),
+            )
+            .chain(
+                inventory
+                    .machines()
+                    .iter()
+                    .filter(|machine| includes_fixed_loadings(machine.kind()))
+                    .map(|machine| {
+                        let pairs = machine.horns().pairs();
+                        let loadings = Self::machine_plates(&plate_counts, machine).iter().fold(
+                            1usize,
+                            |acc, (_, count)| {
+                                // The ways of splitting `count` plates per side between the pairs.
+                                let splits = (1..=pairs).fold(1usize, |splits, pair| {
+                                    splits.saturating_mul(count + pair) / pair
+                                });
+                                acc.saturating_mul(splits)
+                            },
+                        );
+                        (*machine.kind(), loadings)
+                    }),
+            )
+            .collect()
+    }
+
+    /// Estimates the loadings and distance entries for the graph of `graph`'s kind and quantity
+    /// of implements, or for every kind at its usual quantity, and fails if any estimate
+    /// exceeds its limit. Plans are bounded as they are made, so they need no estimate.
+    fn check_limits(
+        inventory: &Inventory,
+        limits: Limits,
+        graph: Option<(BarKind, usize)>,
+    ) -> Result<(), GymError> {
+        let loadings = Self::estimated_loadings(inventory, graph);
+
+        let distance_entries = loadings.iter().fold(0usize, |acc, (_, loadings)| {
+            acc.saturating_add(loadings.saturating_mul(*loadings))
+        });
+
+        let estimates = [
+            (
+                Limit::LoadingsPerBar,
+                loadings.iter().map(|(_, loadings)| loadings).max(),
+            ),
+            (Limit::DistanceEntries, Some(&distance_entries)),
+        ];
+
+        for (limit, estimate) in estimates {
+            if let Some(estimate) = estimate
+                && *estimate > limits.get(limit)
+            {
+                return Err(GymError::TooLarge(limit, *estimate, limits.get(limit)));
+            }
+        }
+
+        Ok(())
+    }
+
+    fn build(inventory: &Inventory, limits: Limits) -> Result<Self, GymError> {
+        let (plates, bars) = (inventory.plates(), inventory.bars());
+        let plate_counts = Self::plate_counts(plates);
+        let scale = Self::inventory_scale(inventory)?;
+        let weighing = inventory.weighing();
 
         let bar_options: HashMap<BarKind, Vec<Bar>> =
             bars.iter().fold(HashMap::new(), |mut acc, bar| {
@@ -97,12 +225,237 @@ impl Gym {
                 acc
             });
 
-        Gym {
-            states,
-            distances,
+        let mut dumbbells = BTreeMap::new();
+        let mut overloaded = HashSet::new();
+        for (kind, bars) in bar_options.iter().sorted_by_key(|(kind, _)| **kind) {
+            dumbbells.extend(Self::held_dumbbells(
+                inventory,
+                bars,
+                scale,
+                kind.default_quantity(),
+                &mut overloaded,
+            )?);
+        }
+
+        let selections: BTreeMap<Selector, Vec<Selection>> = inventory
+            .selectors()
+            .iter()
+            .map(|selector| {
+                let selections = Self::selections(&plate_counts, selector, scale, weighing)?;
+                Ok((selector.clone(), selections))
+            })
+            .collect::<Result<_, GymError>>()?;
+
+        let machine_loadings: BTreeMap<Machine, Vec<MachineLoading>> = inventory
+            .machines()
+            .iter()
+            .map(|machine| {
+                let loadings = Self::machine_loadings(&plate_counts, machine, scale, weighing)?;
+                Ok((*machine, loadings))
+            })
+            .collect::<Result<_, GymError>>()?;
+
+        // Past the point where no plate, adapter or bar is plentiful enough, every quantity of
+        // implements has the same, empty, loadings.
+        let bar_counts = bars.iter().counts();
+        let max_quantity = plate_counts
+            .values()
+            .chain(
+                inventory
+                    .compatibility()
+                    .all_adapters()
+                    .map(|(_, count)| count),
+            )
+            .map(|count| count / 2 + 1)
+            .chain(
+                bar_counts
+                    .iter()
+                    .map(|(bar, count)| count * bar.kind().default_quantity() + 1),
+            )
+            .chain([BarKind::Dumbbell.default_quantity() + 1])
+            .max()
+            .unwrap_or_default();
+
+        let graphs = bar_options
+            .keys()
+            .chain(selections.keys().map(Selector::kind))
+            .chain(machine_loadings.keys().map(Machine::kind))
+            .cartesian_product(1..=max_quantity)
+            .map(|(kind, quantity)| ((*kind, quantity), OnceLock::new()))
+            .collect();
+
+        let weights = Weights::new(
+            dumbbells,
+            selections,
+            machine_loadings,
+            inventory.fixed().to_vec(),
+        );
+
+        Ok(Gym {
+            graphs,
+            max_quantity,
             bar_options,
             weights,
-        }
+            overloaded,
+            scale,
+            inventory: inventory.clone(),
+            limits,
+        })
+    }
+
+    ///
+    /// Builds the state graph of every bar kind, for its usual quantity of implements, now
+    /// rather than on first use, in parallel when the `parallel` feature is enabled.
+    ///
+    /// # Errors
+    /// If a graph can't be built. The error is for the first such bar kind, and is returned
+    /// again by any later plan that needs the graph.
+    ///
+    pub fn prepare(&self) -> Result<(), GymError> {
+        let kinds: Vec<BarKind> = self
+            .graphs
+            .keys()
+            .map(|(kind, _)| *kind)
+            .unique()
+            .sorted()
+            .collect();
+
+        #[cfg(feature = "parallel")]
+        let kind_iter = kinds.into_par_iter();
+        #[cfg(not(feature = "parallel"))]
+        let kind_iter = kinds.into_iter();
+
+        // Collected in kind order first so the reported error doesn't depend on scheduling.
+        kind_iter
+            .map(|kind| self.graph(kind, kind.default_quantity()).map(|_| ()))
+            .collect::<Vec<_>>()
+            .into_iter()
+            .collect()
+    }
+
+    ///
+    /// The state graph for `quantity` identically loaded implements of a kind, if the gym has
+    /// any of that kind.
+    ///
+    /// # Errors
+    /// If the graph's estimated size exceeds the limits the gym was built with, or the
+    /// loadings for an unusual quantity overflow.
+    ///
+    fn graph(&self, bar_kind: BarKind, quantity: usize) -> Result<Option<&StateGraph>, GymError> {
+        let quantity = quantity.min(self.max_quantity);
+        let Some(graph) = self.graphs.get(&(bar_kind, quantity)) else {
+            return Ok(None);
+        };
+
+        graph
+            .get_or_init(|| {
+                Self::check_limits(&self.inventory, self.limits, Some((bar_kind, quantity)))?;
+                self.build_graph(bar_kind, quantity)
+            })
+            .as_ref()
+            .map(Some)
+            .map_err(Clone::clone)
+    }
+
+    /// The kind's usual quantity comes straight from the catalogue. Other quantities load the
+    /// bars afresh, and keep the selectors and machines only if they are no more than the usual
+    /// quantity.
+    fn build_graph(&self, bar_kind: BarKind, quantity: usize) -> Result<StateGraph, GymError> {
+        let dumbbells = if quantity == bar_kind.default_quantity() {
+            self.weights.kind_dumbbells(bar_kind)
+        } else {
+            Self::held_dumbbells(
+                &self.inventory,
+                &self.bars_for(bar_kind, quantity),
+                self.scale,
+                quantity,
+                &mut HashSet::new(),
+            )?
+        };
+
+        let (selections, machine_loadings) = if quantity <= bar_kind.default_quantity() {
+            (
+                self.weights.kind_selections(bar_kind),
+                self.weights.kind_machine_loadings(bar_kind),
+            )
+        } else {
+            (BTreeMap::new(), BTreeMap::new())
+        };
+
+        StateGraph::new(
+            dumbbells,
+            selections,
+            machine_loadings,
+            self.scale,
+            self.inventory.weighing(),
+        )
+    }
+
+    /// The gym's bars of a kind with enough copies for `quantity` implements.
+    fn bars_for(&self, bar_kind: BarKind, quantity: usize) -> Vec<Bar> {
+        let bars: &[Bar] = self.bar_options.get(&bar_kind).map_or(&[], Vec::as_slice);
+        Self::enough_bars(bars, quantity)
+    }
+
+    /// Each of `bars` once, if it is listed often enough for `quantity` implements. Every
+    /// listing stands for its kind's usual quantity, so one dumbbell handle is a pair.
+    pub(crate) fn enough_bars(bars: &[Bar], quantity: usize) -> Vec<Bar> {
+        let counts = bars.iter().counts();
+        bars.iter()
+            .unique()
+            .filter(|bar| counts[bar].saturating_mul(bar.kind().default_quantity()) >= quantity)
+            .copied()
+            .collect()
+    }
+
+    /// The loadings of each bar for `quantity` implements. Loadings beyond a bar's rating are
+    /// dropped, but their weights are added to `overloaded` to explain requirements that only
+    /// they could meet.
+    fn held_dumbbells(
+        inventory: &Inventory,
+        bars: &[Bar],
+        scale: MassScale,
+        quantity: usize,
+        overloaded: &mut HashSet<(BarKind, Mass)>,
+    ) -> Result<BTreeMap<Bar, Vec<Dumbbell>>, GymError> {
+        let plate_counts = Self::plate_counts(inventory.plates());
+
+        #[cfg(feature = "parallel")]
+        let bar_iter = bars.par_iter();
+        #[cfg(not(feature = "parallel"))]
+        let bar_iter = bars.iter();
+
+        // Collected in bar order first so the reported error doesn't depend on scheduling.
+        let dumbbells: BTreeMap<Bar, Vec<Dumbbell>> = bar_iter
+            .map(|bar| {
+                let dumbbells = Self::dumbbells(
+                    &plate_counts,
+                    bar,
+                    inventory.compatibility(),
+                    scale,
+                    inventory.weighing(),
+                    inventory.microloading(),
+                    quantity,
+                )?;
+                Ok((*bar, dumbbells))
+            })
+            .collect::<Vec<Result<_, GymError>>>()
+            .into_iter()
+            .collect::<Result<_, _>>()?;
+
+        Ok(dumbbells
+            .into_iter()
+            .map(|(bar, dumbbells)| {
+                let (held, over): (Vec<Dumbbell>, Vec<Dumbbell>) = dumbbells
+                    .into_iter()
+                    .partition(|dumbbell| bar.holds(*dumbbell.weight()));
+                overloaded.extend(
+                    over.iter()
+                        .map(|dumbbell| (*bar.kind(), *dumbbell.weight())),
+                );
+                (bar, held)
+            })
+            .collect())
     }
 
     #[must_use]
@@ -110,32 +463,263 @@ impl Gym {
         self.weights
     }
 
+    ///
+    /// The weights that can meet requirements like `like`: of its kind, for its quantity of
+    /// implements, and at standard height if it asks for that.
     ///
     /// # Errors
-    /// If it is impossible to construct a dumbbell for a requirement given the user's plates.
+    /// If the weights for an unusual quantity can't be worked out.
+    ///
+    pub(crate) fn achievable(&self, like: Requirement) -> Result<BTreeSet<Mass>, GymError> {
+        let bar_kind = like.bar_kind();
+        if like.quantity() == bar_kind.default_quantity() && !like.standard_height() {
+            return Ok(self.weights.get(bar_kind).into_iter().collect());
+        }
+
+        let mut weights = self
+            .graph(bar_kind, like.quantity())?
+            .map(|graph| graph.weights(like.standard_height()))
+            .unwrap_or_default();
+        weights.extend(
+            self.inventory
+                .fixed()
+                .iter()
+                .filter(|fixed| *fixed.kind() == bar_kind)
+                .map(FixedWeight::weight)
+                .filter(|weight| self.fixed_for(like.with_weight(*weight)).is_some()),
+        );
+
+        Ok(weights)
+    }
+
+    ///
+    /// The minimum number of plate changes needed to perform the requirements in order.
+    /// Requirements met by a fixed weight take no changes. Requirements for an unusual quantity
+    /// of implements are planned apart from the rest of their kind, and the changes between
+    /// them on a shared bar are counted in requirement order.
+    ///
+    /// # Errors
+    /// If it is impossible to construct a dumbbell for a requirement given the user's plates,
+    /// or only possible by loading a bar beyond its rated capacity, or if planning for an
+    /// unusual quantity of implements needs a model larger than the gym's limits.
+    ///
+    pub fn changes(&self, requirements: &[Requirement]) -> Result<u32, GymError> {
+        self.changes_with(requirements, Handling::default())
+    }
+
+    ///
+    /// The plate changes needed to perform the requirements in order, for an athlete limited
+    /// to the plates `handling` allows. Penalised plates steer the plan away from handling
+    /// them, but only plate changes are counted.
+    ///
+    /// # Errors
+    /// If it is impossible to construct a dumbbell for a requirement given the user's plates,
+    /// only possible by loading a bar beyond its rated capacity, or only possible with plates
+    /// the athlete can't handle, or if planning for an unusual quantity of implements needs a
+    /// model larger than the gym's limits.
+    ///
+    pub fn changes_with(
+        &self,
+        requirements: &[Requirement],
+        handling: Handling,
+    ) -> Result<u32, GymError> {
+        let used: Vec<(Requirement, Implement)> = requirements
+            .iter()
+            .copied()
+            .zip(self.plan_requirements(requirements, handling)?)
+            .filter_map(|(req, (used, _))| Some((req, used?)))
+            .collect();
+
+        let mut changes = 0_u32;
+        for (step, to) in used.iter().enumerate() {
+            if let Some(from) = used[..step]
+                .iter()
+                .rev()
+                .find(|(_, from)| Self::same_equipment(from, &to.1))
+            {
+                changes = changes.saturating_add(self.changes_between(from, to)?);
+            }
+        }
+
+        Ok(changes)
+    }
+
+    ///
+    /// The loadings for each bar, in requirement order, that need the fewest plate changes.
+    /// Requirements for an unusual quantity of implements are planned apart from the rest of
+    /// their kind, then merged back into requirement order. A requirement is met by a fixed
+    /// weight of the same kind and weight when there is one, since that takes no changes.
+    /// Equally good loadings are chosen by fewest plates, then lightest plates, then bar order,
+    /// so the same requirements always give the same workout.
+    ///
+    /// # Errors
+    /// If it is impossible to construct a dumbbell for a requirement given the user's plates,
+    /// or only possible by loading a bar beyond its rated capacity, or if planning for an
+    /// unusual quantity of implements needs a model larger than the gym's limits.
     ///
     pub fn workout(&self, requirements: &[Requirement]) -> Result<Workout, GymError> {
-        let requirements_by_kind: HashMap<BarKind, Vec<Requirement>> =
-            requirements.iter().fold(HashMap::new(), |mut acc, req| {
-                acc.entry(req.bar_kind()).or_default().push(*req);
-                acc
-            });
+        self.workout_with(requirements, Handling::default())
+    }
+
+    ///
+    /// The workout for an athlete limited to the plates `handling` allows, trading plate
+    /// changes against penalised plates.
+    ///
+    /// # Errors
+    /// If it is impossible to construct a dumbbell for a requirement given the user's plates,
+    /// only possible by loading a bar beyond its rated capacity, or only possible with plates
+    /// the athlete can't handle, or if planning for an unusual quantity of implements needs a
+    /// model larger than the gym's limits.
+    ///
+    pub fn workout_with(
+        &self,
+        requirements: &[Requirement],
+        handling: Handling,
+    ) -> Result<Workout, GymError> {
+        let mut loadings = BTreeMap::<Bar, Vec<Dumbbell>>::new();
+        let mut selections = BTreeMap::<Selector, Vec<Selection>>::new();
+        let mut machine_loadings = BTreeMap::<Machine, Vec<MachineLoading>>::new();
+        let mut timeline = Vec::new();
 
-        let mut result = HashMap::<Bar, Vec<Dumbbell>>::new();
+        let planned = self.plan_requirements(requirements, handling)?;
+        for (req, (used, implements)) in requirements.iter().zip(planned) {
+            if let Some(used) = used {
+                timeline.push(Step::new(*req, used));
+            }
 
-        for (bar_kind, reqs) in requirements_by_kind {
-            let ordered_dumbbells = self.order_by_kind(bar_kind, &reqs)?;
-            for (bar, dumbbells) in ordered_dumbbells {
-                result
-                    .entry(bar)
-                    .or_default()
-                    .extend(dumbbells.into_iter().cloned().collect::<Vec<_>>());
+            for implement in implements {
+                match implement {
+                    Implement::Loaded(dumbbell) => {
+                        loadings.entry(*dumbbell.bar()).or_default().push(dumbbell);
+                    }
+                    Implement::Selected(selection) => {
+                        let selector = selection.selector().clone();
+                        selections.entry(selector).or_default().push(selection);
+                    }
+                    Implement::Machine(loading) => {
+                        let machine = *loading.machine();
+                        machine_loadings.entry(machine).or_default().push(loading);
+                    }
+                    Implement::Fixed(_) => {}
+                }
             }
         }
 
-        Ok(result.into())
+        Ok(Workout::new(
+            loadings,
+            selections,
+            machine_loadings,
+            timeline,
+        ))
     }
 
+    ///
+    /// For each requirement, the implement used for it and every loaded implement that meets
+    /// it. A requirement met by a fixed weight uses that alone. The rest are planned with the
+    /// others of their kind and quantity of implements.
+    ///
+    /// # Errors
+    /// If a group of requirements can't be planned, as for [`Gym::workout_with`].
+    ///
+    fn plan_requirements(
+        &self,
+        requirements: &[Requirement],
+        handling: Handling,
+    ) -> Result<Vec<Planned>, GymError> {
+        let requirements_by_kind: BTreeMap<(BarKind, usize), Vec<(usize, Requirement)>> =
+            requirements
+                .iter()
+                .enumerate()
+                .fold(BTreeMap::new(), |mut acc, (index, req)| {
+                    acc.entry((req.bar_kind(), req.quantity()))
+                        .or_default()
+                        .push((index, *req));
+                    acc
+                });
+
+        let mut planned = vec![(None, Vec::new()); requirements.len()];
+
+        for ((bar_kind, quantity), reqs) in requirements_by_kind {
+            let (mut indices, mut loaded) = (Vec::new(), Vec::new());
+
+            for (index, req) in reqs {
+                if let Some(fixed) = self.fixed_for(req) {
+                    planned[index].0 = Some(Implement::Fixed(fixed));
+                } else {
+                    indices.push(index);
+                    loaded.push(req);
+                }
+            }
+
+            let ordered = self.order_by_kind(bar_kind, quantity, &loaded, handling)?;
+            for (index, implements) in indices.into_iter().zip(ordered) {
+                planned[index] = implements;
+            }
+        }
+
+        Ok(planned)
+    }
+
+    /// Whether two implements are loadings of the same bar, selector or machine.
+    fn same_equipment(implement1: &Implement, implement2: &Implement) -> bool {
+        match (implement1, implement2) {
+            (Implement::Loaded(dumbbell1), Implement::Loaded(dumbbell2)) => {
+                dumbbell1.bar() == dumbbell2.bar()
+            }
+            (Implement::Selected(selection1), Implement::Selected(selection2)) => {
+                selection1.selector() == selection2.selector()
+            }
+            (Implement::Machine(loading1), Implement::Machine(loading2)) => {
+                loading1.machine() == loading2.machine()
+            }
+            _ => false,
+        }
+    }
+
+    ///
+    /// The plate changes from one loading of a bar, selector or machine to another, each used
+    /// for its requirement. The graph for the smaller quantity of implements has every loading
+    /// of the larger, so both loadings are found there even when their requirements were
+    /// planned apart.
+    ///
+    /// # Errors
+    /// If the graph for the smaller quantity can't be built.
+    ///
+    fn changes_between(
+        &self,
+        (from_req, from): &(Requirement, Implement),
+        (to_req, to): &(Requirement, Implement),
+    ) -> Result<u32, GymError> {
+        let quantity = from_req.quantity().min(to_req.quantity());
+        let graph = self.graph_for(to_req.bar_kind(), quantity, &[*to_req])?;
+
+        Ok(match (graph.locate(from), graph.locate(to)) {
+            (Some((position, from)), Some((_, to))) => graph.distance(position, Some(from), to),
+            _ => u32::MAX,
+        })
+    }
+
+    /// The fixed weight that meets the requirement, if the gym has at least as many of them as
+    /// the requirement asks for. Fixed weights have no plates to set a standard height, so they
+    /// never meet a requirement lifted from the floor.
+    fn fixed_for(&self, requirement: Requirement) -> Option<FixedWeight> {
+        let (bar_kind, weight) = (requirement.bar_kind(), requirement.weight());
+        if requirement.standard_height()
+            || self.weights.fixed_count(bar_kind, weight) < requirement.quantity()
+        {
+            return None;
+        }
+
+        self.weights
+            .fixed(requirement.bar_kind(), requirement.weight())
+            .first()
+            .copied()
+    }
+
+    ///
+    /// For each requirement, the implement the plan loads for it, or the first that already
+    /// meets it, and every loaded bar that meets it in bar order, then the selectors and
+    /// machines that meet it.
     ///
     /// # Errors
     /// If it is impossible to construct a dumbbell for a requirement given the user's plates.
@@ -143,188 +727,521 @@ impl Gym {
     fn order_by_kind(
         &self,
         bar_kind: BarKind,
+        quantity: usize,
         requirements: &[Requirement],
-    ) -> Result<HashMap<Bar, Vec<&Dumbbell>>, GymError> {
+        handling: Handling,
+    ) -> Result<Vec<Planned>, GymError> {
         if requirements.is_empty() {
-            return Ok(HashMap::new());
+            return Ok(Vec::new());
         }
 
-        let optimal_sequence = self.find_optimal_sequence(bar_kind, requirements)?;
+        let graph = self.graph_for(bar_kind, quantity, requirements)?;
+        let optimal_sequence = self.find_optimal_sequence(graph, requirements, handling)?;
+        let bars: &[Bar] = self.bar_options.get(&bar_kind).map_or(&[], Vec::as_slice);
 
-        let mut result = HashMap::<Bar, Vec<&Dumbbell>>::new();
-        let mut requirement_index = 0;
+        Ok(optimal_sequence
+            .iter()
+            .enumerate()
+            .zip(requirements)
+            .map(|((step, state), req)| {
+                let previous = step
+                    .checked_sub(1)
+                    .map(|previous| &optimal_sequence[previous]);
+                let used = graph.loaded(previous, state);
+                let state = graph.state(state);
+                let loaded = bars
+                    .iter()
+                    .filter_map(|bar| state.get(bar))
+                    .filter(|dumbbell| req.matches(dumbbell))
+                    .map(|dumbbell| Implement::Loaded(dumbbell.clone()));
+                let selected = graph
+                    .selectors()
+                    .iter()
+                    .filter_map(|selector| state.selection(selector))
+                    .filter(|selection| *selection.weight() == req.weight())
+                    .map(|selection| Implement::Selected(selection.clone()));
+                let machines = graph
+                    .machines()
+                    .iter()
+                    .filter_map(|machine| state.machine_loading(machine))
+                    .filter(|loading| *loading.resistance() == req.weight())
+                    .map(|loading| Implement::Machine(loading.clone()));
 
-        for state_id in optimal_sequence {
-            let state = &self.states[&bar_kind][&state_id];
-            let bars = self
-                .bar_options
-                .get(&requirements[requirement_index].bar_kind())
-                .ok_or(GymError::ImpossibleRequirement(
-                    requirements[requirement_index],
-                ))?;
+                let implements: Vec<Implement> = loaded.chain(selected).chain(machines).collect();
+                (used.or_else(|| implements.first().cloned()), implements)
+            })
+            .collect())
+    }
 
-            for bar in bars {
-                if let Some(dumbbell) = state.get(bar)
-                    && *dumbbell.weight() == requirements[requirement_index].weight() {
-                        result.entry(*bar).or_default().push(dumbbell);
-                    }
-            }
+    /// Why no state meets the requirement: a bar would be loaded beyond its rating, or the
+    /// weight can't be made at all.
+    fn unmeetable(&self, requirement: Requirement) -> GymError {
+        let key = (requirement.bar_kind(), requirement.weight());
+        let overloaded = if requirement.quantity() == requirement.bar_kind().default_quantity() {
+            self.overloaded.contains(&key)
+        } else {
+            let mut overloaded = HashSet::new();
+            Self::held_dumbbells(
+                &self.inventory,
+                &self.bars_for(requirement.bar_kind(), requirement.quantity()),
+                self.scale,
+                requirement.quantity(),
+                &mut overloaded,
+            )
+            .is_ok()
+                && overloaded.contains(&key)
+        };
 
-            if requirement_index < requirements.len() - 1 {
-                requirement_index += 1;
-            }
+        if overloaded {
+            GymError::OverCapacity(requirement)
+        } else {
+            GymError::ImpossibleRequirement(requirement)
         }
+    }
 
-        Ok(result)
+    fn graph_for(
+        &self,
+        bar_kind: BarKind,
+        quantity: usize,
+        requirements: &[Requirement],
+    ) -> Result<&StateGraph, GymError> {
+        self.graph(bar_kind, quantity)?
+            .ok_or_else(|| self.unmeetable(requirements[0]))
     }
 
-    fn find_states_for_requirement(&self, requirement: Requirement) -> Vec<GymStateId> {
-        let matching_states: Vec<(GymStateId, usize)> = self.states[&requirement.bar_kind()]
+    /// The sequence of states, one per requirement, with the fewest plate changes plus handling
+    /// penalties, among loadings the athlete can handle.
+    fn find_optimal_sequence(
+        &self,
+        graph: &StateGraph,
+        requirements: &[Requirement],
+        handling: Handling,
+    ) -> Result<Vec<GymState>, GymError> {
+        let candidates: Vec<Vec<(usize, LoadingId)>> = requirements
             .iter()
-            .filter_map(|(i, state)| {
-                let bars = self.bar_options.get(&requirement.bar_kind())?;
-
-                for bar in bars {
-                    if let Some(dumbbell) = state.get(bar)
-                        && requirement.matches(dumbbell) {
-                            return Some((*i, dumbbell.plates().len()));
-                        }
+            .map(|req| {
+                let candidates = graph.candidates(*req);
+                if candidates.is_empty() {
+                    return Err(self.unmeetable(*req));
+                }
+
+                let candidates: Vec<(usize, LoadingId)> = candidates
+                    .into_iter()
+                    .filter(|(position, loading)| graph.handles(*position, *loading, handling))
+                    .collect();
+                if candidates.is_empty() {
+                    Err(GymError::Unhandleable(*req))
+                } else {
+                    Ok(candidates)
                 }
-                None
             })
-            .collect();
+            .collect::<Result<_, _>>()?;
 
-        matching_states
-            .into_iter()
-            .sorted_by_key(|(_, complexity): &(GymStateId, usize)| *complexity)
-            .map(|(id, _)| id)
-            .collect()
+        Ok(graph.plan(&candidates, handling, self.limits.get(Limit::PlansPerStep)))
     }
 
-    fn find_optimal_sequence(
-        &self,
-        bar_kind: BarKind,
-        requirements: &[Requirement],
-    ) -> Result<Vec<GymStateId>, GymError> {
-        let requirement_states: Vec<Vec<GymStateId>> = requirements
+    pub(crate) fn plate_counts(plates: &[Plate]) -> BTreeMap<Plate, usize> {
+        plates.iter().fold(BTreeMap::new(), |mut acc, plate| {
+            *acc.entry(*plate).or_default() += 1;
+            acc
+        })
+    }
+
+    pub(crate) fn scale(plates: &[Plate], bars: &[Bar]) -> Result<MassScale, GymError> {
+        let scale = plates
             .iter()
-            .map(|req| self.find_states_for_requirement(*req))
-            .collect();
+            .try_fold(MassScale::default(), |scale, plate| {
+                scale
+                    .including(plate.weight())
+                    .and_then(|scale| scale.including(plate.actual_weight()))
+                    .ok_or(GymError::PlateOverflow(*plate))
+            })?;
 
-        let n = requirement_states.len();
+        bars.iter().try_fold(scale, |scale, bar| {
+            scale
+                .including(bar.weight())
+                .ok_or(GymError::BarOverflow(*bar))
+        })
+    }
 
-        match n {
-            0 => return Ok(vec![]),
-            1 => {
-                return requirement_states[0]
+    /// The scale of the plates and bars, widened to cover every adapter, selector setting and
+    /// machine's resistance.
+    fn inventory_scale(inventory: &Inventory) -> Result<MassScale, GymError> {
+        let scale = inventory.compatibility().all_adapters().try_fold(
+            Self::scale(inventory.plates(), inventory.bars())?,
+            |scale, (adapter, _)| {
+                scale
+                    .including(adapter.weight())
+                    .ok_or(GymError::AdapterOverflow(*adapter))
+            },
+        )?;
+
+        let scale = inventory
+            .selectors()
+            .iter()
+            .try_fold(scale, |scale, selector| {
+                selector
+                    .settings()
                     .iter()
-                    .min_by_key(|id| self.states[&bar_kind][id].plates())
-                    .ok_or(GymError::ImpossibleRequirement(requirements[0]))
-                    .map(|id| vec![*id]);
-            }
-            _ => {}
-        }
+                    .try_fold(scale, |scale, setting| scale.including(*setting))
+                    .ok_or_else(|| GymError::SelectorOverflow(selector.clone()))
+            })?;
+
+        inventory
+            .machines()
+            .iter()
+            .try_fold(scale, |scale, machine| {
+                let plates = inventory
+                    .plates()
+                    .iter()
+                    .filter(|plate| plate.gauge() == machine.gauge())
+                    .flat_map(|plate| [plate.weight(), plate.actual_weight()]);
+
+                [machine.base()]
+                    .into_iter()
+                    .chain(plates)
+                    .try_fold(scale, |scale, mass| {
+                        scale
+                            .including(mass)?
+                            .including(Self::resistance(machine, mass)?)
+                    })
+                    .ok_or(GymError::MachineOverflow(*machine))
+            })
+    }
 
-        let mut dp: Vec<HashMap<GymStateId, (u32, Option<GymStateId>)>> = vec![HashMap::new(); n];
+    /// `mass` on the machine, scaled by its resistance ratio.
+    fn resistance(machine: &Machine, mass: Mass) -> Option<Mass> {
+        mass.get::<kilogram>()
+            .checked_mul(&machine.ratio())
+            .map(Mass::new::<kilogram>)
+    }
+
+    /// Every loading of the bar, lightest first, with the plates that fit it directly or on
+    /// one of its adapters, and enough of them for `quantity` bars loaded alike. With
+    /// `microloading`, loadings with a plate of at most that weight on one side only are
+    /// included for weights no symmetric loading reaches.
+    pub(crate) fn dumbbells(
+        weights_map: &BTreeMap<Plate, usize>,
+        bar: &Bar,
+        compatibility: &Compatibility,
+        scale: MassScale,
+        weighing: Weighing,
+        microloading: Option<Mass>,
+        quantity: usize,
+    ) -> Result<Vec<Dumbbell>, GymError> {
+        let odd_plates = |sleeve_gauge| {
+            Self::odd_plates(
+                weights_map,
+                compatibility,
+                sleeve_gauge,
+                weighing,
+                microloading,
+            )
+        };
 
-        for &state in &requirement_states[0] {
-            dp[0].insert(state, (0, None));
+        let direct = Self::usable_plates(weights_map, compatibility, bar.gauge(), quantity);
+        let mut dumbbells = Self::available_dumbbells(
+            &direct,
+            &odd_plates(bar.gauge()),
+            bar,
+            None,
+            scale,
+            weighing,
+            quantity,
+        )?;
+
+        for adapter in compatibility.adapters(bar, quantity) {
+            let adapted =
+                Self::usable_plates(weights_map, compatibility, adapter.sleeve_gauge(), quantity);
+            dumbbells.extend(Self::available_dumbbells(
+                &adapted,
+                &odd_plates(adapter.sleeve_gauge()),
+                bar,
+                Some(adapter),
+                scale,
+                weighing,
+                quantity,
+            )?);
         }
 
-        for i in 1..n {
-            for &current_state in &requirement_states[i] {
-                let mut min_cost = u32::MAX;
-                let mut best_prev = None;
+        let symmetric: HashSet<Mass> = dumbbells
+            .iter()
+            .filter(|dumbbell| dumbbell.odd_plate().is_none())
+            .map(|dumbbell| *dumbbell.weight())
+            .collect();
+        dumbbells.retain(|dumbbell| {
+            dumbbell.odd_plate().is_none() || !symmetric.contains(dumbbell.weight())
+        });
 
-                let mut prev_states: Vec<_> = dp[i - 1]
-                    .iter()
-                    .map(|(&state, &(cost, _))| (state, cost))
-                    .collect();
-                prev_states.sort_by_key(|&(state, _)| state);
+        dumbbells.sort_by_key(|dumbbell| *dumbbell.weight());
+        Ok(dumbbells)
+    }
 
-                for (prev_state, prev_cost) in prev_states {
-                    let transition_cost = self.distances[&bar_kind][&(prev_state, current_state)];
-                    let total_cost = prev_cost.saturating_add(transition_cost);
+    /// The plates small enough to go on one side of a sleeve alone when microloading within
+    /// `microloading`, with how many of each there are.
+    fn odd_plates(
+        weights_map: &BTreeMap<Plate, usize>,
+        compatibility: &Compatibility,
+        sleeve_gauge: Length,
+        weighing: Weighing,
+        microloading: Option<Mass>,
+    ) -> Vec<(Plate, usize)> {
+        let Some(tolerance) = microloading else {
+            return Vec::new();
+        };
 
-                    if total_cost < min_cost {
-                        min_cost = total_cost;
-                        best_prev = Some(prev_state);
-                    }
-                }
+        weights_map
+            .iter()
+            .filter(|(plate, _)| {
+                plate.weighed(weighing) <= tolerance
+                    && compatibility.fits(plate.gauge(), sleeve_gauge)
+            })
+            .map(|(plate, count)| (*plate, *count))
+            .collect()
+    }
 
-                if min_cost != u32::MAX {
-                    dp[i].insert(current_state, (min_cost, best_prev));
-                }
-            }
-        }
+    /// Every setting of the selector with every combination of add-on plates, lightest first.
+    fn selections(
+        weights_map: &BTreeMap<Plate, usize>,
+        selector: &Selector,
+        scale: MassScale,
+        weighing: Weighing,
+    ) -> Result<Vec<Selection>, GymError> {
+        let add_ons = Self::loadings(&Self::add_on_plates(weights_map, selector), scale, weighing)?;
 
-        let (&final_state, _) = dp[n - 1]
+        let selections = selector
+            .settings()
             .iter()
-            .min_by_key(|(_, (cost, _))| *cost)
-            .ok_or(GymError::ImpossibleRequirement(requirements[n - 1]))?;
+            .cartesian_product(&add_ons)
+            .map(|(setting, (add_on_units, plates))| {
+                let units = scale
+                    .units(*setting)
+                    .and_then(|units| units.checked_add(*add_on_units))
+                    .ok_or_else(|| GymError::SelectorOverflow(selector.clone()))?;
+                Ok((units, *setting, plates.clone()))
+            })
+            .collect::<Result<Vec<_>, GymError>>()?;
 
-        let mut path = Vec::new();
-        let mut current = final_state;
-        path.push(current);
+        Ok(selections
+            .into_iter()
+            .sorted_by_key(|(units, _, _)| *units)
+            .map(|(units, setting, plates)| {
+                Selection::with_weight(selector.clone(), setting, plates, scale.mass(units))
+            })
+            .collect())
+    }
 
-        for i in (0..n - 1).rev() {
-            if let Some((_, Some(prev))) = dp[i + 1].get(&current) {
-                current = *prev;
-                path.push(current);
+    /// Every way of loading the machine's horns, lightest first. Each pair of horns is loaded
+    /// from the plates the pairs before it left over.
+    fn machine_loadings(
+        weights_map: &BTreeMap<Plate, usize>,
+        machine: &Machine,
+        scale: MassScale,
+        weighing: Weighing,
+    ) -> Result<Vec<MachineLoading>, GymError> {
+        let overflow = || GymError::MachineOverflow(*machine);
+        let base = scale.units(machine.base()).ok_or_else(overflow)?;
+
+        let mut loadings = vec![(base, Vec::<Vec<Plate>>::new())];
+        for _ in 0..machine.horns().pairs() {
+            let mut next = Vec::new();
+
+            for (units, pairs) in loadings {
+                let remaining: Vec<(Plate, usize)> = Self::machine_plates(weights_map, machine)
+                    .into_iter()
+                    .map(|(plate, count)| {
+                        let used = pairs
+                            .iter()
+                            .flatten()
+                            .filter(|used| **used == plate)
+                            .count();
+                        (plate, count - used)
+                    })
+                    .collect();
+
+                for (pair_units, plates) in Self::loadings(&remaining, scale, weighing)? {
+                    let units = pair_units
+                        .checked_mul(2)
+                        .and_then(|pair_units| pair_units.checked_add(units))
+                        .ok_or_else(overflow)?;
+                    let mut pairs = pairs.clone();
+                    pairs.push(plates);
+                    next.push((units, pairs));
+                }
             }
+
+            loadings = next;
         }
 
-        path.reverse();
-        Ok(path)
+        loadings
+            .into_iter()
+            .sorted_by_key(|(units, _)| *units)
+            .map(|(units, pairs)| {
+                let resistance =
+                    Self::resistance(machine, scale.mass(units)).ok_or_else(overflow)?;
+                Ok(MachineLoading::with_resistance(
+                    *machine, &pairs, resistance,
+                ))
+            })
+            .collect()
     }
 
-    fn dumbbells(weights_map: &BTreeMap<Plate, usize>, bar: &Bar) -> Vec<Dumbbell> {
-        Self::available_dumbbells(
-            &weights_map
-                .iter()
-                .rev()
-                .filter(|(plate, count)| {
-                    *count >= &bar.kind().required_similar_plates() && plate.gauge() == bar.gauge()
-                })
-                .map(|(plate, count)| (*plate, count / bar.kind().required_similar_plates()))
-                .flat_map(|(plate, count)| vec![plate; count])
-                .collect::<Vec<_>>(),
-            bar,
-        )
-        .into_iter()
-        .sorted()
-        .collect()
+    /// The plates that fit the machine's horns, heaviest first, with how many can go on each
+    /// side.
+    fn machine_plates(
+        weights_map: &BTreeMap<Plate, usize>,
+        machine: &Machine,
+    ) -> Vec<(Plate, usize)> {
+        weights_map
+            .iter()
+            .rev()
+            .filter(|(plate, count)| **count >= 2 && plate.gauge() == machine.gauge())
+            .map(|(plate, count)| (*plate, count / 2))
+            .collect()
     }
 
-    fn available_dumbbells(plates: &[Plate], bar: &Bar) -> HashSet<Dumbbell> {
-        plates
+    /// The plates that fit on top of the selector, heaviest first, with how many there are.
+    fn add_on_plates(
+        weights_map: &BTreeMap<Plate, usize>,
+        selector: &Selector,
+    ) -> Vec<(Plate, usize)> {
+        weights_map
             .iter()
-            .powerset()
-            .map(|plates| Dumbbell::new(plates.into_iter().copied().collect(), *bar))
-            .collect::<HashSet<_>>()
+            .rev()
+            .filter(|(plate, _)| Some(plate.gauge()) == selector.add_on_gauge())
+            .map(|(plate, count)| (*plate, *count))
+            .collect()
     }
 
-    fn graph(states: &HashMap<GymStateId, GymState>) -> UnGraphMap<GymStateId, u32> {
-        let mut graph = UnGraphMap::<GymStateId, u32>::new();
+    /// The plates that fit a sleeve of `sleeve_gauge`, heaviest first, with how many can go
+    /// on each side of each of `quantity` bars.
+    fn usable_plates(
+        weights_map: &BTreeMap<Plate, usize>,
+        compatibility: &Compatibility,
+        sleeve_gauge: Length,
+        quantity: usize,
+    ) -> Vec<(Plate, usize)> {
+        let required = quantity.saturating_mul(2);
 
-        for id in states.keys() {
-            graph.add_node(*id);
-        }
+        weights_map
+            .iter()
+            .rev()
+            .filter(|(plate, count)| {
+                **count >= required && compatibility.fits(plate.gauge(), sleeve_gauge)
+            })
+            .map(|(plate, count)| (*plate, count / required))
+            .collect()
+    }
 
-        for ((i1, state1), (i2, state2)) in states.iter().tuple_combinations() {
-            if state1.adjacent(state2) {
-                graph.add_edge(*i1, *i2, 1);
-            }
-        }
+    /// Every loading of the bar with `plates`, lightest first, each with an `adapter` on both
+    /// sleeves if given. Each loading is followed by its variants with one of `odd_plates` on a
+    /// single side, where enough of that plate are left over for `quantity` bars.
+    fn available_dumbbells(
+        plates: &[(Plate, usize)],
+        odd_plates: &[(Plate, usize)],
+        bar: &Bar,
+        adapter: Option<Adapter>,
+        scale: MassScale,
+        weighing: Weighing,
+        quantity: usize,
+    ) -> Result<Vec<Dumbbell>, GymError> {
+        let adapter_units = match adapter {
+            Some(adapter) => scale
+                .units(adapter.weight())
+                .and_then(|units| units.checked_mul(2))
+                .ok_or(GymError::AdapterOverflow(adapter))?,
+            None => 0,
+        };
+        let bar_units = scale
+            .units(bar.weight())
+            .and_then(|units| units.checked_add(adapter_units))
+            .ok_or(GymError::BarOverflow(*bar))?;
 
-        graph
+        Self::loadings(plates, scale, weighing)?
+            .into_iter()
+            .map(|(units, plates)| {
+                let units = units
+                    .checked_mul(2)
+                    .and_then(|units| units.checked_add(bar_units))
+                    .ok_or(GymError::BarOverflow(*bar))?;
+
+                let mut dumbbells = Vec::with_capacity(odd_plates.len() + 1);
+                for (odd_plate, count) in odd_plates {
+                    // Every bar takes the plate on both sides, plus one odd plate of its own.
+                    let used =
+                        plates.iter().filter(|plate| *plate == odd_plate).count() * quantity * 2;
+                    if count.saturating_sub(used) < quantity {
+                        continue;
+                    }
+
+                    let units = scale
+                        .units(odd_plate.weighed(weighing))
+                        .and_then(|odd_units| odd_units.checked_add(units))
+                        .ok_or(GymError::PlateOverflow(*odd_plate))?;
+                    dumbbells.push(
+                        Dumbbell::with_weight(plates.clone(), *bar, adapter, scale.mass(units))
+                            .with_odd_plate(*odd_plate),
+                    );
+                }
+
+                dumbbells.insert(
+                    0,
+                    Dumbbell::with_weight(plates, *bar, adapter, scale.mass(units)),
+                );
+                Ok(dumbbells)
+            })
+            .flatten_ok()
+            .collect()
+    }
+
+    /// Every combination of the plates with its weight in units of `scale`, lightest first,
+    /// weighing each plate as `weighing` says. Combinations are built one plate size at a time
+    /// as a multiset of plate counts so that none is produced twice, and summed with checked
+    /// arithmetic.
+    fn loadings(
+        plates: &[(Plate, usize)],
+        scale: MassScale,
+        weighing: Weighing,
+    ) -> Result<Vec<(i64, Vec<Plate>)>, GymError> {
+        let loadings = plates.iter().try_fold(
+            vec![(0_i64, Vec::<Plate>::new())],
+            |loadings, (plate, count)| {
+                let plate_units = scale
+                    .units(plate.weighed(weighing))
+                    .ok_or(GymError::PlateOverflow(*plate))?;
+
+                let mut next = Vec::with_capacity(loadings.len() * (count + 1));
+                for (mut units, mut loading) in loadings {
+                    next.push((units, loading.clone()));
+
+                    for _ in 0..*count {
+                        units = units
+                            .checked_add(plate_units)
+                            .ok_or(GymError::PlateOverflow(*plate))?;
+                        loading.push(*plate);
+                        next.push((units, loading.clone()));
+                    }
+                }
+
+                Ok(next)
+            },
+        )?;
+
+        Ok(loadings
+            .into_iter()
+            .sorted_by_key(|(units, _)| *units)
+            .collect())
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use std::str::FromStr;
+    use std::{
+        collections::{BTreeMap, HashSet},
+        str::FromStr,
+    };
 
+    use itertools::Itertools;
     use uom::num_rational::Rational64;
     use uom::si::{
         length::centimeter,
@@ -332,7 +1249,11 @@ mod tests {
         rational64::{Length, Mass},
     };
 
-    use crate::{Bar, BarKind, Gym, Plate, Requirement};
+    use crate::test_support::{cm, fixture_plates, kg, olympic_plates};
+    use crate::{
+        Bar, BarKind, Compatibility, Dumbbell, Gym, GymError, Inventory, Limit, Limits, Plate,
+        Requirement, Weighing, Weights,
+    };
 
     fn plate_r(weight_kg: Rational64, count: usize) -> Vec<Plate> {
         let mut v = Vec::with_capacity(count);
@@ -345,6 +1266,271 @@ mod tests {
         v
     }
 
+    #[test]
+    fn dumbbells_match_powerset_enumeration() {
+        let bar = Bar::new(kg(15), cm(5), BarKind::Barbell);
+
+        let plate_counts = Gym::plate_counts(&fixture_plates());
+        let scale = Gym::scale(&fixture_plates(), &[bar]).unwrap();
+        let dumbbells = Gym::dumbbells(
+            &plate_counts,
+            &bar,
+            &Compatibility::default(),
+            scale,
+            Weighing::Nominal,
+            None,
+            1,
+        )
+        .unwrap();
+
+        let usable: Vec<Plate> = plate_counts
+            .iter()
+            .rev()
+            .flat_map(|(plate, count)| vec![*plate; count / 2])
+            .collect();
+        let powerset: HashSet<Dumbbell> = usable
+            .iter()
+            .powerset()
+            .map(|plates| Dumbbell::new(plates.into_iter().copied().collect(), bar))
+            .collect();
+        let expected: Vec<Dumbbell> = powerset.into_iter().sorted().collect();
+
+        assert_eq!(
+            dumbbells.iter().cloned().collect::<HashSet<_>>(),
+            expected.iter().cloned().collect::<HashSet<_>>()
+        );
+        assert_eq!(
+            dumbbells.iter().map(Dumbbell::weight).collect::<Vec<_>>(),
+            expected.iter().map(Dumbbell::weight).collect::<Vec<_>>()
+        );
+        assert_eq!(
+            Weights::new(
+                BTreeMap::from([(bar, dumbbells)]),
+                BTreeMap::new(),
+                BTreeMap::new(),
+                Vec::new()
+            )
+            .get(BarKind::Barbell),
+            Weights::new(
+                BTreeMap::from([(bar, expected)]),
+                BTreeMap::new(),
+                BTreeMap::new(),
+                Vec::new()
+            )
+            .get(BarKind::Barbell)
+        );
+    }
+
+    #[test]
+    fn try_new_rejects_inventory_above_limits() {
+        let bar = Bar::new(kg(15), cm(5), BarKind::Barbell);
+
+        let result = Gym::try_new(&fixture_plates(), &[bar], Limits::new(100, 1000, 100_000));
+
+        assert!(matches!(
+            result,
+            Err(GymError::TooLarge(Limit::LoadingsPerBar, 112, 100))
+        ));
+        assert!(Gym::try_new(&fixture_plates(), &[bar], Limits::default()).is_ok());
+    }
+
+    #[test]
+    fn plans_per_step_bounds_planning_not_the_gym() {
+        let handles: Vec<Bar> = [2, 3, 4, 5]
+            .map(|weight| Bar::new(kg(weight), cm(5), BarKind::Dumbbell))
+            .to_vec();
+        let requirements: Vec<Requirement> = ["12d", "17d", "8d", "13d", "18d", "12d", "9d"]
+            .map(|req| Requirement::from_str(req).unwrap())
+            .to_vec();
+
+        // Each handle takes four loadings, so there are more combinations across all four
+        // handles than plans kept.
+        let gym = Gym::try_new(&fixture_plates(), &handles, Limits::new(100, 100, 100_000))
+            .expect("each handle takes few enough loadings");
+        let greedy = Gym::try_new(&fixture_plates(), &handles, Limits::new(100, 1, 100_000))
+            .expect("each handle takes few enough loadings");
+
+        let changes = gym.changes(&requirements).unwrap();
+        assert_eq!(
+            greedy.workout(&requirements).unwrap().timeline().len(),
+            requirements.len()
+        );
+        assert!(changes <= greedy.changes(&requirements).unwrap());
+    }
+
+    #[test]
+    fn try_from_inventory_counts_microloading_against_limits() {
+        let bar = Bar::new(kg(15), cm(5), BarKind::Barbell);
+        let limits = Limits::new(200, 1000, 100_000);
+
+        let mut inventory = Inventory::new(fixture_plates(), vec![bar]);
+        assert!(Gym::try_from_inventory(&inventory, limits).is_ok());
+
+        // Every loading may also take one of the 2.5kg plates on a single side.
+        inventory.allow_microloading(kg((5, 2)));
+        assert!(matches!(
+            Gym::try_from_inventory(&inventory, limits),
+            Err(GymError::TooLarge(Limit::LoadingsPerBar, 224, 200))
+        ));
+    }
+
+    #[test]
+    fn unusual_quantity_is_checked_against_limits_before_building() {
+        let handle = Bar::new(kg(2), cm(5), BarKind::Dumbbell);
+
+        let gym = Gym::try_new(
+            &fixture_plates(),
+            &[handle],
+            Limits::new(100, 1000, 100_000),
+        )
+        .expect("a pair of handles shares few enough plates");
+
+        assert!(gym.workout(&[Requirement::from_str("7d").unwrap()]).is_ok());
+        assert!(matches!(
+            gym.workout(&[Requirement::from_str("1x7d").unwrap()]),
+            Err(GymError::TooLarge(Limit::LoadingsPerBar, 112, 100))
+        ));
+    }
+
+    #[test]
+    fn try_new_reports_overflowing_plate() {
+        let bar = Bar::new(kg(15), cm(5), BarKind::Barbell);
+
+        let mut plates = plate_r(Rational64::new(1, 1 << 40), 2);
+        plates.extend(plate_r(Rational64::new(1, 3_i64.pow(30)), 2));
+
+        let result = Gym::try_new(&plates, &[bar], Limits::default());
+
+        assert!(matches!(result, Err(GymError::PlateOverflow(plate)) if plate == plates[2]));
+    }
+
+    /// CI runs the tests with and without the `parallel` feature, so pinning the plan here
+    /// checks that both builds produce it.
+    #[test]
+    fn prepared_plan_is_the_same_with_or_without_parallel() {
+        let bars = [
+            Bar::new(kg(2), cm(3), BarKind::Dumbbell),
+            Bar::new(kg(3), cm(3), BarKind::Dumbbell),
+            Bar::new(kg(20), cm(3), BarKind::Barbell),
+        ];
+        let plates: Vec<Plate> = [(5, 4), (10, 4), (20, 4), (40, 2)]
+            .into_iter()
+            .flat_map(|(quarters, count)| vec![Plate::new(kg((quarters, 4)), cm(3)); count])
+            .collect();
+        let requirements: Vec<Requirement> = [
+            "12d", "17d", "7d", "8d", "19.5d", "12d", "40b", "30b", "50b",
+        ]
+        .into_iter()
+        .map(|req| Requirement::from_str(req).unwrap())
+        .collect();
+
+        let prepared = Gym::new(&plates, &bars);
+        prepared.prepare().unwrap();
+        let lazy = Gym::new(&plates, &bars);
+
+        let plan = prepared.workout(&requirements).unwrap().to_string();
+        let expected = [
+            "Workout:",
+            "Dumbbell(3cm) 2kg",
+            "  - [5] (12kg)",
+            "  - [5, 2.5] (17kg)",
+            "  - [2.5] (7kg)",
+            "  - [5, 2.5, 1.25] (19.5kg)",
+            "  - [5] (12kg)",
+            "Dumbbell(3cm) 3kg",
+            "  - [2.5] (8kg)",
+            "Barbell(3cm) 20kg",
+            "  - [5, 5] (40kg)",
+            "  - [5] (30kg)",
+            "  - [10, 5] (50kg)",
+        ];
+
+        assert_eq!(plan.lines().collect::<Vec<_>>(), expected);
+        assert_eq!(plan, lazy.workout(&requirements).unwrap().to_string());
+        assert_eq!(prepared.changes(&requirements).unwrap(), 14);
+    }
+
+    #[test]
+    fn gym_is_shareable_across_threads() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<Gym>();
+    }
+
+    #[test]
+    fn workout_plans_many_bars_and_sets_with_bounded_plans() {
+        let handles: Vec<Bar> = (4..12)
+            .map(|half_kg| Bar::new(kg((half_kg, 2)), cm(5), BarKind::Dumbbell))
+            .collect();
+
+        let mut plates = olympic_plates((5, 4), 8);
+        plates.extend(olympic_plates((5, 2), 8));
+        plates.extend(olympic_plates(5, 8));
+        let gym = Gym::new(&plates, &handles);
+
+        // Every combination of loadings across eight handles would be far too many plans to
+        // keep, so this only finishes because each step keeps a bounded number of them.
+        let requirements: Vec<Requirement> = [
+            "12d", "22d", "17d", "27d", "12d", "7d", "14.5d", "19.5d", "24.5d", "9.5d", "22d",
+            "17d",
+        ]
+        .into_iter()
+        .cycle()
+        .take(40)
+        .map(|req| Requirement::from_str(req).unwrap())
+        .collect();
+
+        let workout = gym.workout(&requirements).unwrap();
+        let one_handle = Gym::new(&plates, &handles[..1]);
+
+        assert_eq!(workout.timeline().len(), requirements.len());
+        assert_eq!(one_handle.changes(&requirements).unwrap(), 78);
+        assert_eq!(gym.changes(&requirements).unwrap(), 49);
+    }
+
+    #[test]
+    fn workout_breaks_ties_by_fewest_then_lightest_plates() {
+        let bar = Bar::new(kg(15), cm(5), BarKind::Barbell);
+
+        let requirements = vec![Requirement::from_str("25b").unwrap()];
+        let plans: Vec<String> = (0..3)
+            .map(|_| {
+                let gym = Gym::new(&fixture_plates(), &[bar]);
+                gym.workout(&requirements).unwrap().to_string()
+            })
+            .collect();
+
+        let gym = Gym::new(&fixture_plates(), &[bar]);
+        let workout = gym.workout(&requirements).unwrap();
+
+        assert_eq!(
+            workout.get(bar)[0].plates(),
+            &plate_r(Rational64::from_integer(5), 1)[..]
+        );
+        assert!(plans.iter().all(|plan| *plan == plans[0]));
+
+        // One plate a side either way, but 2.5kg plates on the heavier bar are lighter.
+        let heavier = Bar::new(kg(20), cm(5), BarKind::Barbell);
+        let gym = Gym::new(&fixture_plates(), &[bar, heavier]);
+        let workout = gym.workout(&requirements).unwrap();
+
+        assert!(workout.get(bar).is_empty());
+        assert_eq!(
+            workout.get(heavier)[0].plates(),
+            &plate_r(Rational64::new(5, 2), 1)[..]
+        );
+
+        // Identical loadings on both bars, so the first in bar order wins, whichever way round
+        // the inventory lists them.
+        let rated = heavier.with_capacity(kg(200));
+        for bars in [[heavier, rated], [rated, heavier]] {
+            let gym = Gym::new(&fixture_plates(), &bars);
+            let workout = gym.workout(&requirements).unwrap();
+
+            assert_eq!(workout.get(heavier).len(), 1);
+            assert!(workout.get(rated).is_empty());
+        }
+    }
+
     #[test]
     fn workout_for_30b_40b_45b_with_given_inventory() {
         let bar = Bar::new(
@@ -1,18 +1,51 @@
 use thiserror::Error;
 
-use crate::Requirement;
+use crate::{Adapter, Bar, Limit, Machine, Plate, Requirement, Selector};
 
-#[derive(Error, Debug)]
+#[derive(Error, Clone, Debug)]
 pub enum GymError {
     #[error("Cannot construct {0} with available plates and bars.")]
     ImpossibleRequirement(Requirement),
 
+    #[error("Cannot construct {0} without loading a bar beyond its rated capacity.")]
+    OverCapacity(Requirement),
+
+    #[error("Cannot construct {0} with plates the athlete can handle.")]
+    Unhandleable(Requirement),
+
+    #[error("Gym too large: {1} {0} estimated, above the limit of {2}.")]
+    TooLarge(Limit, usize, usize),
+
+    #[error("Weight arithmetic overflowed while adding plate {0}.")]
+    PlateOverflow(Plate),
+
+    #[error("Weight arithmetic overflowed while loading bar {0}.")]
+    BarOverflow(Bar),
+
+    #[error("Weight arithmetic overflowed while fitting {0}.")]
+    AdapterOverflow(Adapter),
+
+    #[error("Weight arithmetic overflowed while setting {0}.")]
+    SelectorOverflow(Selector),
+
+    #[error("Weight arithmetic overflowed while loading machine {0}.")]
+    MachineOverflow(Machine),
+
+    #[error("Weight arithmetic overflowed while progressing to session {0}.")]
+    ProgressionOverflow(usize),
+
     #[error("Invalid requirement: {0}")]
     InvalidRequirement(String),
 
-    #[error("Invalid weight: {0} - must be of the format <number>(d | b).")]
+    #[error("Invalid weight: {0} - must be of the format <number>(d | b | k | m).")]
     InvalidWeight(String),
 
-    #[error("Unknown bar kind: {0} - must be one of: d, b.")]
+    #[error("Invalid percentage: {0} - must be a decimal number.")]
+    InvalidPercentage(String),
+
+    #[error("No max recorded for {0}.")]
+    UnknownMax(String),
+
+    #[error("Unknown bar kind: {0} - must be one of: d, b, k, m.")]
     InvalidBarKind(String),
 }
@@ -1,59 +1,194 @@
-use std::collections::HashMap;
+use std::collections::BTreeMap;
 
 use derive_more::Display;
 
-use crate::{Bar, Dumbbell};
+use crate::{Bar, Dumbbell, Machine, MachineLoading, Plate, Selection, Selector};
 
+/// The index of a dumbbell within its bar's list of loadings.
 #[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Display)]
-pub struct GymStateId(pub usize);
+pub struct LoadingId(pub usize);
 
-#[derive(Clone, Debug, PartialEq, Eq)]
+/// The loading of each bar, selector or machine of a kind, in the order of the kind's
+/// [`LoadingTable`], or `None` for one a plan hasn't used yet.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub(crate) struct GymState {
-    state: HashMap<Bar, Dumbbell>,
+    loadings: Box<[Option<LoadingId>]>,
 }
 
 impl GymState {
+    /// The state before any of `positions` has been loaded.
     #[must_use]
-    pub fn new(state: HashMap<Bar, Dumbbell>) -> Self {
-        GymState { state }
+    pub fn new(positions: usize) -> Self {
+        GymState {
+            loadings: vec![None; positions].into_boxed_slice(),
+        }
     }
 
+    /// The same state with the loading at `position` changed.
     #[must_use]
-    pub fn adjacent(&self, other: &Self) -> bool {
-        let mut adjacencies = 0;
+    pub fn with(&self, position: usize, loading: LoadingId) -> Self {
+        let mut loadings = self.loadings.clone();
+        loadings[position] = Some(loading);
+        GymState { loadings }
+    }
 
-        for (bar, dumbbell) in &self.state {
-            if let Some(other_dumbbell) = other.get(bar) {
-                let same = dumbbell == other_dumbbell;
-                let adjacent = dumbbell.adjacent(other_dumbbell);
+    /// The same state with the positions `keep` rules out treated as unused.
+    #[must_use]
+    pub fn keeping(&self, keep: &[bool]) -> Self {
+        GymState {
+            loadings: self
+                .loadings
+                .iter()
+                .zip(keep)
+                .map(|(loading, keep)| loading.filter(|_| *keep))
+                .collect(),
+        }
+    }
 
-                if !(same || adjacent) {
-                    return false;
-                }
+    #[must_use]
+    pub fn loadings(&self) -> &[Option<LoadingId>] {
+        &self.loadings
+    }
+}
 
-                if adjacent {
-                    adjacencies += 1;
-                }
+/// The loadings each bar, selector and machine of a kind can take, shared by every state of
+/// that kind. Bars take the first positions, followed by selectors and then machines.
+pub(crate) struct LoadingTable {
+    bars: Vec<Bar>,
+    dumbbells: Vec<Vec<Dumbbell>>,
+    selectors: Vec<Selector>,
+    selections: Vec<Vec<Selection>>,
+    machines: Vec<Machine>,
+    machine_loadings: Vec<Vec<MachineLoading>>,
+}
 
-                if adjacencies > 1 {
-                    return false;
-                }
-            }
+impl LoadingTable {
+    #[must_use]
+    pub fn new(
+        dumbbells: BTreeMap<Bar, Vec<Dumbbell>>,
+        selections: BTreeMap<Selector, Vec<Selection>>,
+        machine_loadings: BTreeMap<Machine, Vec<MachineLoading>>,
+    ) -> Self {
+        let (bars, dumbbells) = dumbbells.into_iter().unzip();
+        let (selectors, selections) = selections.into_iter().unzip();
+        let (machines, machine_loadings) = machine_loadings.into_iter().unzip();
+        LoadingTable {
+            bars,
+            dumbbells,
+            selectors,
+            selections,
+            machines,
+            machine_loadings,
         }
+    }
+
+    /// The number of bars, selectors and machines.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.bars.len() + self.selectors.len() + self.machines.len()
+    }
+
+    #[must_use]
+    pub fn position(&self, bar: &Bar) -> Option<usize> {
+        self.bars.binary_search(bar).ok()
+    }
+
+    #[must_use]
+    pub fn selector_position(&self, selector: &Selector) -> Option<usize> {
+        let index = self.selectors.binary_search(selector).ok()?;
+        Some(self.bars.len() + index)
+    }
+
+    /// The loadings of the bar at `position`, lightest first.
+    #[must_use]
+    pub fn dumbbells(&self, position: usize) -> &[Dumbbell] {
+        self.dumbbells.get(position).map_or(&[], Vec::as_slice)
+    }
+
+    /// The selections of the selector at `position`, lightest first.
+    #[must_use]
+    pub fn selections(&self, position: usize) -> &[Selection] {
+        position
+            .checked_sub(self.bars.len())
+            .and_then(|selector| self.selections.get(selector))
+            .map_or(&[], Vec::as_slice)
+    }
 
-        adjacencies == 1
+    #[must_use]
+    pub fn selectors(&self) -> &[Selector] {
+        &self.selectors
+    }
+
+    #[must_use]
+    pub fn machine_position(&self, machine: &Machine) -> Option<usize> {
+        let index = self.machines.binary_search(machine).ok()?;
+        Some(self.bars.len() + self.selectors.len() + index)
+    }
+
+    /// The loadings of the machine at `position`, lightest first.
+    #[must_use]
+    pub fn machine_loadings(&self, position: usize) -> &[MachineLoading] {
+        position
+            .checked_sub(self.bars.len() + self.selectors.len())
+            .and_then(|machine| self.machine_loadings.get(machine))
+            .map_or(&[], Vec::as_slice)
+    }
+
+    #[must_use]
+    pub fn machines(&self) -> &[Machine] {
+        &self.machines
+    }
+
+    /// The stacks of plates a loading puts on, innermost first: one side of a bar, a
+    /// selector's add-ons, or one horn of each pair of a machine.
+    #[must_use]
+    pub fn stacks(&self, position: usize, loading: LoadingId) -> Vec<Vec<Plate>> {
+        if let Some(dumbbell) = self.dumbbells(position).get(loading.0) {
+            let [side, _] = dumbbell.sides();
+            return vec![side];
+        }
+        if let Some(selection) = self.selections(position).get(loading.0) {
+            return vec![selection.plates().to_vec()];
+        }
+
+        self.machine_loadings(position)
+            .get(loading.0)
+            .map(|loading| loading.pairs().map(<[Plate]>::to_vec).collect())
+            .unwrap_or_default()
+    }
+}
+
+/// A state read through the loading table it indexes into.
+#[derive(Clone, Copy)]
+pub(crate) struct GymStateRef<'a> {
+    state: &'a GymState,
+    table: &'a LoadingTable,
+}
+
+impl<'a> GymStateRef<'a> {
+    #[must_use]
+    pub fn new(state: &'a GymState, table: &'a LoadingTable) -> Self {
+        GymStateRef { state, table }
+    }
+
+    #[must_use]
+    pub fn get(self, bar: &Bar) -> Option<&'a Dumbbell> {
+        let position = self.table.position(bar)?;
+        let loading = self.state.loadings[position]?;
+        self.table.dumbbells(position).get(loading.0)
     }
 
     #[must_use]
-    pub fn get(&self, bar: &Bar) -> Option<&Dumbbell> {
-        self.state.get(bar)
+    pub fn machine_loading(self, machine: &Machine) -> Option<&'a MachineLoading> {
+        let position = self.table.machine_position(machine)?;
+        let loading = self.state.loadings[position]?;
+        self.table.machine_loadings(position).get(loading.0)
     }
 
     #[must_use]
-    pub fn plates(&self) -> usize {
-        self.state
-            .values()
-            .map(|dumbbell| dumbbell.plates().len())
-            .sum()
+    pub fn selection(self, selector: &Selector) -> Option<&'a Selection> {
+        let position = self.table.selector_position(selector)?;
+        let loading = self.state.loadings[position]?;
+        self.table.selections(position).get(loading.0)
     }
 }
@@ -0,0 +1,103 @@
+use uom::si::rational64::Mass;
+
+use crate::Plate;
+
+/// Which plates one athlete can handle in a session. Plates above the excluded weight are never
+/// loaded, and each plate above the penalised weight put on or taken off counts as `penalty`
+/// extra plate changes. Applied when planning, so one gym serves every athlete.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Handling {
+    excluded_above: Option<Mass>,
+    penalised_above: Option<(Mass, u32)>,
+}
+
+impl Handling {
+    /// The same constraints, never loading plates heavier than `weight`.
+    #[must_use]
+    pub fn excluding_above(self, weight: Mass) -> Self {
+        Handling {
+            excluded_above: Some(weight),
+            ..self
+        }
+    }
+
+    /// The same constraints, counting each plate heavier than `weight` that is handled as
+    /// `penalty` extra plate changes.
+    #[must_use]
+    pub fn penalising_above(self, weight: Mass, penalty: u32) -> Self {
+        Handling {
+            penalised_above: Some((weight, penalty)),
+            ..self
+        }
+    }
+
+    #[must_use]
+    pub fn allows(self, plate: &Plate) -> bool {
+        self.excluded_above
+            .is_none_or(|weight| plate.weight() <= weight)
+    }
+
+    #[must_use]
+    pub fn penalty(self, plate: &Plate) -> u32 {
+        match self.penalised_above {
+            Some((weight, penalty)) if plate.weight() > weight => penalty,
+            _ => 0,
+        }
+    }
+
+    /// Whether any plate carries a penalty, so planning can skip counting them otherwise.
+    pub(crate) fn penalises(self) -> bool {
+        self.penalised_above.is_some()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use crate::test_support::{cm, kg};
+    use crate::{Bar, BarKind, Gym, GymError, Handling, Plate, Requirement, Workout};
+
+    #[test]
+    fn handling_excludes_or_penalises_heavy_plates_at_solve_time() {
+        let bar = Bar::new(kg(20), cm(5), BarKind::Barbell);
+        let plate = |weight| Plate::new(kg(weight), cm(5));
+
+        let plates = [25, 20, 5]
+            .into_iter()
+            .flat_map(|weight| [plate(weight); 2]);
+        let gym = Gym::new(&plates.collect::<Vec<_>>(), &[bar]);
+        let requirements = ["30b", "70b"].map(|req| Requirement::from_str(req).unwrap());
+        let last = |workout: Workout| workout.get(bar)[1].plates().to_vec();
+
+        assert_eq!(last(gym.workout(&requirements).unwrap()), [plate(25)]);
+
+        let penalised = Handling::default().penalising_above(kg(20), 5);
+        assert_eq!(
+            last(gym.workout_with(&requirements, penalised).unwrap()),
+            [plate(20), plate(5)]
+        );
+        assert_eq!(gym.changes_with(&requirements, penalised).unwrap(), 3);
+
+        for sets in [1, 2] {
+            let requirements = vec![Requirement::from_str("70b").unwrap(); sets];
+            let workout = gym.workout_with(&requirements, penalised).unwrap();
+            assert!(
+                workout
+                    .get(bar)
+                    .iter()
+                    .all(|dumbbell| dumbbell.plates() == [plate(20), plate(5)])
+            );
+        }
+
+        let excluded = Handling::default().excluding_above(kg(20));
+        assert_eq!(
+            last(gym.workout_with(&requirements, excluded).unwrap()),
+            [plate(20), plate(5)]
+        );
+        assert!(matches!(
+            gym.changes_with(&[Requirement::from_str("80b").unwrap()], excluded),
+            Err(GymError::Unhandleable(_))
+        ));
+    }
+}
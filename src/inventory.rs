@@ -0,0 +1,104 @@
+use uom::si::rational64::{Length, Mass};
+
+use crate::{Adapter, Bar, Compatibility, FixedWeight, Machine, Plate, Selector, Weighing};
+
+/// Everything a gym has to lift with.
+#[derive(Clone, Debug, Default)]
+pub struct Inventory {
+    plates: Vec<Plate>,
+    bars: Vec<Bar>,
+    fixed: Vec<FixedWeight>,
+    selectors: Vec<Selector>,
+    machines: Vec<Machine>,
+    compatibility: Compatibility,
+    weighing: Weighing,
+    microloading: Option<Mass>,
+}
+
+impl Inventory {
+    #[must_use]
+    pub fn new(plates: Vec<Plate>, bars: Vec<Bar>) -> Self {
+        Inventory {
+            plates,
+            bars,
+            ..Inventory::default()
+        }
+    }
+
+    pub fn add_plate(&mut self, plate: Plate) {
+        self.plates.push(plate);
+    }
+
+    pub fn add_fixed(&mut self, fixed: FixedWeight) {
+        self.fixed.push(fixed);
+    }
+
+    pub fn add_selector(&mut self, selector: Selector) {
+        self.selectors.push(selector);
+    }
+
+    pub fn add_machine(&mut self, machine: Machine) {
+        self.machines.push(machine);
+    }
+
+    /// Lets plates of `plate_gauge` go on bars or adapters of `sleeve_gauge`.
+    pub fn add_fit(&mut self, plate_gauge: Length, sleeve_gauge: Length) {
+        self.compatibility.add_fit(plate_gauge, sleeve_gauge);
+    }
+
+    pub fn add_adapter(&mut self, adapter: Adapter, count: usize) {
+        self.compatibility.add_adapter(adapter, count);
+    }
+
+    /// Plans with each plate's nominal or actual weight. Nominal by default.
+    pub fn set_weighing(&mut self, weighing: Weighing) {
+        self.weighing = weighing;
+    }
+
+    /// Lets the sides of a bar differ by one plate of at most `tolerance`, for jumps smaller
+    /// than a pair of the lightest plates. Off by default.
+    pub fn allow_microloading(&mut self, tolerance: Mass) {
+        self.microloading = Some(tolerance);
+    }
+
+    #[must_use]
+    pub fn plates(&self) -> &[Plate] {
+        &self.plates
+    }
+
+    #[must_use]
+    pub fn bars(&self) -> &[Bar] {
+        &self.bars
+    }
+
+    #[must_use]
+    pub fn fixed(&self) -> &[FixedWeight] {
+        &self.fixed
+    }
+
+    #[must_use]
+    pub fn selectors(&self) -> &[Selector] {
+        &self.selectors
+    }
+
+    #[must_use]
+    pub fn machines(&self) -> &[Machine] {
+        &self.machines
+    }
+
+    #[must_use]
+    pub fn compatibility(&self) -> &Compatibility {
+        &self.compatibility
+    }
+
+    #[must_use]
+    pub fn weighing(&self) -> Weighing {
+        self.weighing
+    }
+
+    /// The heaviest plate allowed on one side only, if microloading is allowed.
+    #[must_use]
+    pub fn microloading(&self) -> Option<Mass> {
+        self.microloading
+    }
+}
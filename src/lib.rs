@@ -2,25 +2,59 @@
 
 mod bar;
 mod bar_kind;
+mod compatibility;
 mod dumbbell;
+mod fixed_weight;
 mod format;
 mod gym;
 mod gym_error;
 mod gym_state;
+mod handling;
+mod inventory;
+mod limits;
+mod machine;
+mod mass_scale;
+mod percentage;
 mod plate;
+mod progression;
+mod purchase;
 mod requirement;
+mod rounding;
+mod selector;
+mod state_graph;
+#[cfg(test)]
+mod test_support;
+mod warm_up;
+mod weighing;
 mod weights;
 mod workout;
 
 pub(crate) use gym_state::GymState;
-pub(crate) use gym_state::GymStateId;
+pub(crate) use gym_state::GymStateRef;
+pub(crate) use gym_state::LoadingId;
+pub(crate) use gym_state::LoadingTable;
+pub(crate) use mass_scale::MassScale;
+pub(crate) use state_graph::StateGraph;
 
 pub use bar::Bar;
 pub use bar_kind::BarKind;
+pub use compatibility::{Adapter, Compatibility};
 pub use dumbbell::Dumbbell;
+pub use fixed_weight::FixedWeight;
 pub use gym::Gym;
 pub use gym_error::GymError;
+pub use handling::Handling;
+pub use inventory::Inventory;
+pub use limits::{Limit, Limits};
+pub use machine::{Horns, Machine, MachineLoading};
+pub use percentage::{MaxTarget, Maxes, PercentageRequirement};
 pub use plate::Plate;
+pub use progression::{Increment, LinearProgression, Progression};
+pub use purchase::{PriceList, Purchase};
 pub use requirement::Requirement;
+pub use rounding::Rounding;
+pub use selector::{Selection, Selector};
+pub use warm_up::Ramp;
+pub use weighing::Weighing;
 pub use weights::Weights;
-pub use workout::Workout;
+pub use workout::{Implement, Step, Workout};
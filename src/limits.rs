@@ -0,0 +1,52 @@
+use strum::Display;
+
+/// A part of the gym model whose size can be limited.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Display)]
+pub enum Limit {
+    #[strum(to_string = "loadings per bar")]
+    LoadingsPerBar,
+    #[strum(to_string = "plans per step")]
+    PlansPerStep,
+    #[strum(to_string = "distance table entries")]
+    DistanceEntries,
+}
+
+/// Upper bounds on the size of a gym model. Loadings and distance entries are checked before
+/// they are built. Plans per step is how many partial plans a workout carries from one
+/// requirement to the next; past it only the cheapest are kept.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Limits {
+    loadings_per_bar: usize,
+    plans_per_step: usize,
+    distance_entries: usize,
+}
+
+impl Limits {
+    /// Plans carried between requirements unless limited otherwise. Enough to plan a session
+    /// on a dozen bars in well under a second.
+    pub const DEFAULT_PLANS_PER_STEP: usize = 1000;
+
+    #[must_use]
+    pub fn new(loadings_per_bar: usize, plans_per_step: usize, distance_entries: usize) -> Self {
+        Limits {
+            loadings_per_bar,
+            plans_per_step,
+            distance_entries,
+        }
+    }
+
+    #[must_use]
+    pub fn get(self, limit: Limit) -> usize {
+        match limit {
+            Limit::LoadingsPerBar => self.loadings_per_bar,
+            Limit::PlansPerStep => self.plans_per_step,
+            Limit::DistanceEntries => self.distance_entries,
+        }
+    }
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Limits::new(usize::MAX, Self::DEFAULT_PLANS_PER_STEP, usize::MAX)
+    }
+}
@@ -0,0 +1,204 @@
+use std::fmt::Display;
+
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
+use uom::{
+    num_rational::Rational64,
+    si::rational64::{Length, Mass},
+};
+
+use crate::{BarKind, Plate, format};
+
+/// How many loading horns a machine has. Horns come in left and right pairs which are always
+/// loaded the same.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum Horns {
+    Two,
+    Four,
+}
+
+impl Horns {
+    #[must_use]
+    pub fn count(self) -> usize {
+        self.pairs() * 2
+    }
+
+    #[must_use]
+    pub fn pairs(self) -> usize {
+        match self {
+            Horns::Two => 1,
+            Horns::Four => 2,
+        }
+    }
+}
+
+/// A plate-loaded machine such as a leg press or hack squat. The sled and its plates are
+/// scaled by `ratio` to give the effective resistance requirements are written against.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct Machine {
+    base: Mass,
+    gauge: Length,
+    horns: Horns,
+    ratio: Rational64,
+}
+
+impl Machine {
+    #[must_use]
+    pub fn new(base: Mass, gauge: Length, horns: Horns, ratio: Rational64) -> Self {
+        Machine {
+            base,
+            gauge,
+            horns,
+            ratio,
+        }
+    }
+
+    /// The weight of the empty sled, before the resistance ratio is applied.
+    #[must_use]
+    pub fn base(&self) -> Mass {
+        self.base
+    }
+
+    #[must_use]
+    pub fn gauge(&self) -> Length {
+        self.gauge
+    }
+
+    #[must_use]
+    pub fn horns(&self) -> Horns {
+        self.horns
+    }
+
+    #[must_use]
+    pub fn ratio(&self) -> Rational64 {
+        self.ratio
+    }
+
+    #[must_use]
+    pub fn kind(&self) -> &BarKind {
+        &BarKind::Machine
+    }
+}
+
+impl Display for Machine {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Machine({}cm, {} horns, x{}) {}kg",
+            format::length_to_dec_string(self.gauge),
+            self.horns.count(),
+            format::rational_to_dec_string(self.ratio),
+            format::mass_to_dec_string(self.base)
+        )
+    }
+}
+
+/// The plates on each horn of a machine, with the effective resistance they give.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct MachineLoading {
+    machine: Machine,
+    horns: Vec<Vec<Plate>>,
+    resistance: Mass,
+}
+
+impl MachineLoading {
+    /// `pairs` holds the plates of each pair of horns, heaviest first, and `resistance` is what
+    /// they give through the machine's ratio.
+    #[must_use]
+    pub(crate) fn with_resistance(
+        machine: Machine,
+        pairs: &[Vec<Plate>],
+        resistance: Mass,
+    ) -> Self {
+        MachineLoading {
+            machine,
+            horns: pairs
+                .iter()
+                .flat_map(|plates| [plates.clone(), plates.clone()])
+                .collect(),
+            resistance,
+        }
+    }
+
+    #[must_use]
+    pub fn machine(&self) -> &Machine {
+        &self.machine
+    }
+
+    /// The plates on each horn, heaviest first, with the two horns of a pair next to each
+    /// other.
+    #[must_use]
+    pub fn horns(&self) -> &[Vec<Plate>] {
+        &self.horns
+    }
+
+    /// The plates on one horn of each pair.
+    pub(crate) fn pairs(&self) -> impl Iterator<Item = &[Plate]> {
+        self.horns.iter().step_by(2).map(Vec::as_slice)
+    }
+
+    #[must_use]
+    pub fn resistance(&self) -> &Mass {
+        &self.resistance
+    }
+}
+
+impl Display for MachineLoading {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let horns = self
+            .horns
+            .iter()
+            .map(|plates| {
+                let plates = plates
+                    .iter()
+                    .map(|p| p.weight())
+                    .map(format::mass_to_dec_string)
+                    .join(", ");
+                format!("[{plates}]")
+            })
+            .join(" ");
+
+        write!(
+            f,
+            "{} ({}kg)",
+            horns,
+            format::mass_to_dec_string(self.resistance)
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use uom::num_rational::Rational64;
+
+    use crate::test_support::{cm, kg, olympic_plates};
+    use crate::{Gym, Horns, Inventory, Machine, Requirement};
+
+    #[test]
+    fn workout_loads_machine_horns_for_effective_resistance() {
+        let leg_press = Machine::new(kg(50), cm(5), Horns::Four, Rational64::new(1, 2));
+
+        let mut plates = olympic_plates(20, 4);
+        plates.extend(olympic_plates(10, 4));
+
+        let mut inventory = Inventory::new(plates, Vec::new());
+        inventory.add_machine(leg_press);
+        let gym = Gym::from_inventory(&inventory);
+
+        let requirements = vec![
+            Requirement::from_str("45m").unwrap(),
+            Requirement::from_str("55m").unwrap(),
+        ];
+
+        let workout = gym.workout(&requirements).expect("workout should succeed");
+        let loadings = workout.machine_loadings(&leg_press);
+
+        assert_eq!(gym.changes(&requirements).unwrap(), 1);
+        assert_eq!(loadings.len(), 2);
+        assert_eq!(loadings[0].horns().len(), 4);
+        assert_eq!(loadings[0].horns().iter().map(Vec::len).sum::<usize>(), 2);
+        assert_eq!(*loadings[1].resistance(), kg(55));
+    }
+}
@@ -0,0 +1,48 @@
+use uom::{
+    num_rational::Rational64,
+    num_traits::CheckedMul,
+    si::{mass::kilogram, rational64::Mass},
+};
+
+/// Counts masses in whole units of the smallest fraction of a kilogram an inventory needs, so
+/// the solver compares plain integers instead of normalising rationals.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct MassScale {
+    per_kilogram: i64,
+}
+
+impl MassScale {
+    /// The scale extended so `mass` is also a whole number of units, unless that overflows.
+    #[must_use]
+    pub fn including(self, mass: Mass) -> Option<Self> {
+        let denominator = *mass.get::<kilogram>().denom();
+        let per_kilogram =
+            (self.per_kilogram / gcd(self.per_kilogram, denominator)).checked_mul(denominator)?;
+
+        Some(MassScale { per_kilogram })
+    }
+
+    /// `mass` in units, if it is a whole number of them that fits.
+    #[must_use]
+    pub fn units(self, mass: Mass) -> Option<i64> {
+        let units = mass
+            .get::<kilogram>()
+            .checked_mul(&Rational64::from_integer(self.per_kilogram))?;
+        units.is_integer().then(|| units.to_integer())
+    }
+
+    #[must_use]
+    pub fn mass(self, units: i64) -> Mass {
+        Mass::new::<kilogram>(Rational64::new(units, self.per_kilogram))
+    }
+}
+
+impl Default for MassScale {
+    fn default() -> Self {
+        MassScale { per_kilogram: 1 }
+    }
+}
+
+fn gcd(a: i64, b: i64) -> i64 {
+    if b == 0 { a.abs() } else { gcd(b, a % b) }
+}
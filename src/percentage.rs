@@ -0,0 +1,341 @@
+use std::{collections::HashMap, fmt::Display, str::FromStr};
+
+use rational_extensions::{MinMax, try_from_dec_str};
+use uom::{num_rational::Rational64, si::rational64::Mass};
+
+use crate::{BarKind, Gym, GymError, Requirement, Rounding, Weights, format};
+
+/// The max a percentage is taken of: either a bar kind's or a named lift's.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum MaxTarget {
+    Kind(BarKind),
+    Lift(String),
+}
+
+impl Display for MaxTarget {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MaxTarget::Kind(kind) => write!(f, "{kind}"),
+            MaxTarget::Lift(lift) => write!(f, "{lift}"),
+        }
+    }
+}
+
+/// Maxes (or training maxes) per bar kind and per named lift.
+#[derive(Clone, Debug, Default)]
+pub struct Maxes {
+    kinds: HashMap<BarKind, Mass>,
+    lifts: HashMap<String, (Mass, BarKind)>,
+}
+
+impl Maxes {
+    #[must_use]
+    pub fn new() -> Self {
+        Maxes::default()
+    }
+
+    pub fn insert_kind(&mut self, kind: BarKind, max: Mass) {
+        self.kinds.insert(kind, max);
+    }
+
+    pub fn insert_lift(&mut self, lift: &str, max: Mass, kind: BarKind) {
+        self.lifts.insert(lift.to_lowercase(), (max, kind));
+    }
+
+    #[must_use]
+    pub fn get(&self, target: &MaxTarget) -> Option<(Mass, BarKind)> {
+        match target {
+            MaxTarget::Kind(kind) => self.kinds.get(kind).map(|max| (*max, *kind)),
+            MaxTarget::Lift(lift) => self.lifts.get(&lift.to_lowercase()).copied(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PercentageRequirement {
+    percentage: Rational64,
+    max: MaxTarget,
+    quantity: Option<usize>,
+    standard_height: bool,
+}
+
+impl PercentageRequirement {
+    #[must_use]
+    pub fn new(percentage: Rational64, max: MaxTarget) -> Self {
+        PercentageRequirement {
+            percentage,
+            max,
+            quantity: None,
+            standard_height: false,
+        }
+    }
+
+    /// The same requirement, for `quantity` identically loaded implements rather than the
+    /// usual quantity for the max's bar kind.
+    #[must_use]
+    pub fn with_quantity(self, quantity: usize) -> Self {
+        PercentageRequirement {
+            quantity: Some(quantity),
+            ..self
+        }
+    }
+
+    /// The same requirement, lifted from the floor at standard height.
+    #[must_use]
+    pub fn at_standard_height(self) -> Self {
+        PercentageRequirement {
+            standard_height: true,
+            ..self
+        }
+    }
+
+    #[must_use]
+    pub fn percentage(&self) -> Rational64 {
+        self.percentage
+    }
+
+    #[must_use]
+    pub fn max(&self) -> &MaxTarget {
+        &self.max
+    }
+}
+
+impl Display for PercentageRequirement {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Some(quantity) = self.quantity {
+            write!(f, "{quantity}{}", Requirement::QUANTITY_SEPARATOR)?;
+        }
+
+        write!(
+            f,
+            "{}% {}",
+            format::rational_to_dec_string(self.percentage),
+            self.max
+        )?;
+
+        if self.standard_height {
+            write!(f, " {}", Requirement::STANDARD_HEIGHT_SUFFIX)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl FromStr for PercentageRequirement {
+    type Err = GymError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Some(rest) = s
+            .trim_end()
+            .strip_suffix(Requirement::STANDARD_HEIGHT_SUFFIX)
+        {
+            return PercentageRequirement::from_str(rest)
+                .map(PercentageRequirement::at_standard_height);
+        }
+
+        if let Some((quantity, rest)) = s.trim_start().split_once(Requirement::QUANTITY_SEPARATOR)
+            && let Ok(quantity) = quantity.parse::<usize>()
+        {
+            if quantity == 0 {
+                return Err(GymError::InvalidRequirement(s.to_string()));
+            }
+            return PercentageRequirement::from_str(rest).map(|req| req.with_quantity(quantity));
+        }
+
+        let (percentage, max) = s
+            .split_once('%')
+            .ok_or(GymError::InvalidRequirement(s.to_string()))?;
+
+        let min_max = MinMax::new(0, 10).unwrap();
+        let percentage: Rational64 = try_from_dec_str(percentage.trim(), &min_max)
+            .map_err(|_| GymError::InvalidPercentage(percentage.to_string()))?;
+
+        let max = max.trim().to_lowercase();
+        if max.is_empty() {
+            return Err(GymError::InvalidRequirement(s.to_string()));
+        }
+
+        let max = BarKind::from_str(&max).map_or(MaxTarget::Lift(max), MaxTarget::Kind);
+
+        Ok(PercentageRequirement::new(percentage, max))
+    }
+}
+
+impl Gym {
+    ///
+    /// Resolves a percentage of a max to an achievable requirement.
+    ///
+    /// # Errors
+    /// If there is no max for the requirement or no achievable weight in the rounding direction
+    /// for its quantity of implements and height.
+    ///
+    pub fn resolve(
+        &self,
+        requirement: &PercentageRequirement,
+        maxes: &Maxes,
+        rounding: Rounding,
+    ) -> Result<Requirement, GymError> {
+        let (max, bar_kind) = maxes
+            .get(requirement.max())
+            .ok_or(GymError::UnknownMax(requirement.max().to_string()))?;
+
+        let target = max * requirement.percentage() / Rational64::from_integer(100);
+
+        let mut like = Requirement::new(target, bar_kind)
+            .with_quantity(requirement.quantity.unwrap_or(bar_kind.default_quantity()));
+        if requirement.standard_height {
+            like = like.at_standard_height();
+        }
+
+        Weights::snap_in(&self.achievable(like)?, target, rounding)
+            .map(|weight| like.with_weight(weight))
+            .ok_or(GymError::ImpossibleRequirement(like))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use uom::num_rational::Rational64;
+
+    use crate::test_support::{cm, kg};
+    use crate::{
+        Bar, BarKind, Gym, GymError, MaxTarget, Maxes, PercentageRequirement, Plate, Requirement,
+        Rounding,
+    };
+
+    /// A 20kg bar with pairs of 20kg, 10kg and 5kg plates: 20kg to 90kg in 10kg steps, with a
+    /// 100kg barbell max and a 120kg squat.
+    fn gym_and_maxes() -> (Gym, Maxes) {
+        let gauge = cm(5);
+        let bar = Bar::new(kg(20), gauge, BarKind::Barbell);
+        let plates: Vec<Plate> = [20, 10, 5]
+            .into_iter()
+            .flat_map(|weight| [Plate::new(kg(weight), gauge); 2])
+            .collect();
+
+        let mut maxes = Maxes::new();
+        maxes.insert_kind(BarKind::Barbell, kg(100));
+        maxes.insert_lift("Squat", kg(120), BarKind::Barbell);
+
+        (Gym::new(&plates, &[bar]), maxes)
+    }
+
+    #[test]
+    fn parse_percentage_of_kind_and_lift() {
+        let kind = PercentageRequirement::from_str("80%b").unwrap();
+        let lift = PercentageRequirement::from_str("72.5% Squat").unwrap();
+
+        assert_eq!(kind.percentage(), Rational64::from_integer(80));
+        assert_eq!(kind.max(), &MaxTarget::Kind(BarKind::Barbell));
+        assert_eq!(lift.percentage(), Rational64::new(145, 2));
+        assert_eq!(lift.max(), &MaxTarget::Lift("squat".to_string()));
+        assert!(PercentageRequirement::from_str("80b").is_err());
+
+        let floor = PercentageRequirement::from_str("80% deadlift @floor").unwrap();
+        assert_eq!(
+            floor,
+            PercentageRequirement::new(
+                Rational64::from_integer(80),
+                MaxTarget::Lift("deadlift".to_string())
+            )
+            .at_standard_height()
+        );
+        assert_eq!(floor.to_string(), "80% deadlift @floor");
+
+        let single = PercentageRequirement::from_str("1x80%d").unwrap();
+        assert_eq!(
+            single,
+            PercentageRequirement::new(
+                Rational64::from_integer(80),
+                MaxTarget::Kind(BarKind::Dumbbell)
+            )
+            .with_quantity(1)
+        );
+        assert_eq!(single.to_string(), "1x80% Dumbbell");
+        assert_eq!(
+            PercentageRequirement::from_str("2x80% squat @floor")
+                .unwrap()
+                .to_string(),
+            "2x80% squat @floor"
+        );
+        assert!(PercentageRequirement::from_str("0x80%d").is_err());
+        assert!(matches!(
+            PercentageRequirement::from_str("eighty%b"),
+            Err(GymError::InvalidPercentage(_))
+        ));
+    }
+
+    #[test]
+    fn resolve_snaps_a_percentage_of_the_max() {
+        let (gym, maxes) = gym_and_maxes();
+        let resolve = |requirement: &str, rounding| {
+            gym.resolve(
+                &PercentageRequirement::from_str(requirement).unwrap(),
+                &maxes,
+                rounding,
+            )
+            .map(Requirement::weight)
+        };
+
+        assert_eq!(resolve("80%b", Rounding::Down).unwrap(), kg(80));
+        assert_eq!(resolve("75%b", Rounding::Down).unwrap(), kg(70));
+        assert_eq!(resolve("75%b", Rounding::Up).unwrap(), kg(80));
+        assert_eq!(resolve("75%b", Rounding::Nearest).unwrap(), kg(70));
+        assert_eq!(resolve("50% squat", Rounding::Down).unwrap(), kg(60));
+        assert!(matches!(
+            resolve("95%b", Rounding::Up),
+            Err(GymError::ImpossibleRequirement(_))
+        ));
+    }
+
+    #[test]
+    fn resolve_looks_lifts_up_regardless_of_case() {
+        let (gym, maxes) = gym_and_maxes();
+        let squat = PercentageRequirement::new(
+            Rational64::from_integer(50),
+            MaxTarget::Lift("Squat".to_string()),
+        );
+        let bench = PercentageRequirement::new(
+            Rational64::from_integer(50),
+            MaxTarget::Lift("Bench".into()),
+        );
+
+        assert_eq!(
+            gym.resolve(&squat, &maxes, Rounding::Down)
+                .unwrap()
+                .weight(),
+            kg(60)
+        );
+        assert!(matches!(
+            gym.resolve(&bench, &maxes, Rounding::Down),
+            Err(GymError::UnknownMax(_))
+        ));
+    }
+
+    #[test]
+    fn resolve_keeps_the_requested_quantity() {
+        let gauge = cm(3);
+        let handle = Bar::new(kg(2), gauge, BarKind::Dumbbell);
+
+        let mut plates = vec![Plate::new(kg(5), gauge); 2];
+        plates.extend(vec![Plate::new(kg((5, 2)), gauge); 2]);
+        plates.extend(vec![Plate::new(kg((5, 4)), gauge); 4]);
+        let gym = Gym::new(&plates, &[handle]);
+
+        let mut maxes = Maxes::new();
+        maxes.insert_kind(BarKind::Dumbbell, kg(17));
+        let requirement = PercentageRequirement::from_str("100%d").unwrap();
+
+        let pair = gym
+            .resolve(&requirement, &maxes, Rounding::Nearest)
+            .unwrap();
+        let single = gym
+            .resolve(&requirement.with_quantity(1), &maxes, Rounding::Nearest)
+            .unwrap();
+
+        assert_eq!(pair.weight(), kg((9, 2)));
+        assert_eq!((single.weight(), single.quantity()), (kg(17), 1));
+    }
+}
@@ -1,31 +1,96 @@
 use std::fmt::Display;
 
 use serde::{Deserialize, Serialize};
-use uom::si::rational64::{Length, Mass};
+use uom::{
+    num_rational::Rational64,
+    si::{
+        length::centimeter,
+        rational64::{Length, Mass},
+    },
+};
 
-use crate::format;
+use crate::{Weighing, format};
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
 pub struct Plate {
     weight: Mass,
     gauge: Length,
+    #[serde(default)]
+    diameter: Option<Length>,
+    #[serde(default)]
+    actual: Option<Mass>,
 }
 
 impl Plate {
     #[must_use]
     pub fn new(weight: Mass, gauge: Length) -> Self {
-        Plate { weight, gauge }
+        Plate {
+            weight,
+            gauge,
+            diameter: None,
+            actual: None,
+        }
     }
 
+    /// The same plate, measured to weigh `actual` rather than its nominal weight.
+    #[must_use]
+    pub fn with_actual(self, actual: Mass) -> Self {
+        Plate {
+            actual: Some(actual),
+            ..self
+        }
+    }
+
+    /// The same plate, `diameter` across.
+    #[must_use]
+    pub fn with_diameter(self, diameter: Length) -> Self {
+        Plate {
+            diameter: Some(diameter),
+            ..self
+        }
+    }
+
+    /// The diameter of a full-size plate, which sets a bar at standard height off the floor.
+    #[must_use]
+    pub fn standard_diameter() -> Length {
+        Length::new::<centimeter>(Rational64::from_integer(45))
+    }
+
+    /// The nominal weight printed on the plate.
     #[must_use]
     pub fn weight(self) -> Mass {
         self.weight
     }
 
+    /// The measured weight, or the nominal weight if the plate hasn't been measured.
+    #[must_use]
+    pub fn actual_weight(self) -> Mass {
+        self.actual.unwrap_or(self.weight)
+    }
+
+    #[must_use]
+    pub fn weighed(self, weighing: Weighing) -> Mass {
+        match weighing {
+            Weighing::Nominal => self.weight,
+            Weighing::Actual => self.actual_weight(),
+        }
+    }
+
     #[must_use]
     pub fn gauge(self) -> Length {
         self.gauge
     }
+
+    #[must_use]
+    pub fn diameter(self) -> Option<Length> {
+        self.diameter
+    }
+
+    #[must_use]
+    pub fn is_full_size(self) -> bool {
+        self.diameter
+            .is_some_and(|diameter| diameter >= Self::standard_diameter())
+    }
 }
 
 impl Display for Plate {
@@ -38,3 +103,49 @@ impl Display for Plate {
         )
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use crate::test_support::{cm, kg};
+    use crate::{Bar, BarKind, FixedWeight, Gym, Implement, Inventory, Plate, Requirement};
+
+    #[test]
+    fn floor_requirement_uses_a_full_size_plate() {
+        let bar = Bar::new(kg(20), cm(5), BarKind::Barbell);
+        let bumper = Plate::new(kg(10), cm(5)).with_diameter(Plate::standard_diameter());
+
+        let mut plates = vec![Plate::new(kg(20), cm(5)); 2];
+        plates.extend(vec![Plate::new(kg(10), cm(5)); 2]);
+        plates.extend(vec![bumper; 2]);
+
+        let gym = Gym::new(&plates, &[bar]);
+        let plan = |requirement: &str| {
+            let workout = gym
+                .workout(&[Requirement::from_str(requirement).unwrap()])
+                .expect("workout should succeed");
+            workout.get(bar)[0].clone()
+        };
+
+        assert!(!plan("60b").at_standard_height());
+        assert!(plan("60b @floor").at_standard_height());
+        assert!(plan("60b@floor").plates().contains(&bumper));
+
+        let mut inventory = Inventory::new(plates, vec![bar]);
+        inventory.add_fixed(FixedWeight::new(kg(60), BarKind::Barbell));
+        let gym = Gym::from_inventory(&inventory);
+        let implement = |requirement: &str| {
+            let workout = gym
+                .workout(&[Requirement::from_str(requirement).unwrap()])
+                .expect("workout should succeed");
+            workout.timeline()[0].implement().clone()
+        };
+
+        assert!(matches!(implement("60b"), Implement::Fixed(_)));
+        assert!(matches!(
+            implement("60b @floor"),
+            Implement::Loaded(dumbbell) if dumbbell.at_standard_height()
+        ));
+    }
+}
@@ -0,0 +1,306 @@
+use uom::{
+    num_rational::Rational64,
+    num_traits::{CheckedAdd, CheckedMul},
+    si::{mass::kilogram, rational64::Mass},
+};
+
+use crate::{BarKind, Gym, GymError, Requirement, Rounding, Weights};
+
+/// How much the target weight grows from one session to the next.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Increment {
+    /// A fixed weight added every session.
+    Fixed(Mass),
+    /// A fraction of the previous session's target, compounded.
+    Relative(Rational64),
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct LinearProgression {
+    start: Mass,
+    bar_kind: BarKind,
+    increment: Increment,
+    sessions: usize,
+    quantity: usize,
+    standard_height: bool,
+}
+
+impl LinearProgression {
+    #[must_use]
+    pub fn new(start: Mass, bar_kind: BarKind, increment: Increment, sessions: usize) -> Self {
+        LinearProgression {
+            start,
+            bar_kind,
+            increment,
+            sessions,
+            quantity: bar_kind.default_quantity(),
+            standard_height: false,
+        }
+    }
+
+    /// The same progression, with every session for `quantity` identically loaded implements.
+    #[must_use]
+    pub fn with_quantity(self, quantity: usize) -> Self {
+        LinearProgression { quantity, ..self }
+    }
+
+    /// The same progression, with every session lifted from the floor at standard height.
+    #[must_use]
+    pub fn at_standard_height(self) -> Self {
+        LinearProgression {
+            standard_height: true,
+            ..self
+        }
+    }
+
+    #[must_use]
+    pub fn bar_kind(self) -> BarKind {
+        self.bar_kind
+    }
+
+    /// A session's requirement at `weight`.
+    #[must_use]
+    pub fn requirement(self, weight: Mass) -> Requirement {
+        let requirement = Requirement::new(weight, self.bar_kind).with_quantity(self.quantity);
+        if self.standard_height {
+            requirement.at_standard_height()
+        } else {
+            requirement
+        }
+    }
+
+    ///
+    /// The unrounded target weight of each session.
+    ///
+    /// # Errors
+    /// If a session's target is too large to represent, as compounding a small fraction over
+    /// many sessions can be.
+    ///
+    pub fn targets(self) -> Result<Vec<Mass>, GymError> {
+        let mut target = self.start.get::<kilogram>();
+        (0..self.sessions)
+            .map(|session| {
+                if session > 0 {
+                    target = self
+                        .next(target)
+                        .ok_or(GymError::ProgressionOverflow(session + 1))?;
+                }
+                Ok(Mass::new::<kilogram>(target))
+            })
+            .collect()
+    }
+
+    /// The target after `previous`, in kilograms, unless it overflows.
+    fn next(self, previous: Rational64) -> Option<Rational64> {
+        match self.increment {
+            Increment::Fixed(increment) => previous.checked_add(&increment.get::<kilogram>()),
+            Increment::Relative(fraction) => Rational64::from_integer(1)
+                .checked_add(&fraction)
+                .and_then(|factor| previous.checked_mul(&factor)),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct Progression {
+    sessions: Vec<Requirement>,
+    stalls: Vec<usize>,
+}
+
+impl Progression {
+    /// One requirement per session, snapped to an achievable weight.
+    #[must_use]
+    pub fn sessions(&self) -> &[Requirement] {
+        &self.sessions
+    }
+
+    /// Indices of sessions whose weight rounded to the same load as the session before.
+    #[must_use]
+    pub fn stalls(&self) -> &[usize] {
+        &self.stalls
+    }
+
+    #[must_use]
+    pub fn stalled(&self) -> bool {
+        !self.stalls.is_empty()
+    }
+}
+
+impl Gym {
+    ///
+    /// Snaps each session of a progression onto the weights achievable in this gym for its
+    /// quantity of implements and height.
+    ///
+    /// # Errors
+    /// If a session's target has no achievable weight in the rounding direction or overflows,
+    /// or the weights for an unusual quantity of implements can't be worked out.
+    ///
+    pub fn progression(
+        &self,
+        progression: LinearProgression,
+        rounding: Rounding,
+    ) -> Result<Progression, GymError> {
+        let weights = self.achievable(progression.requirement(Mass::default()))?;
+
+        let sessions = progression
+            .targets()?
+            .into_iter()
+            .map(|target| {
+                Weights::snap_in(&weights, target, rounding)
+                    .map(|weight| progression.requirement(weight))
+                    .ok_or(GymError::ImpossibleRequirement(
+                        progression.requirement(target),
+                    ))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let stalls = sessions
+            .windows(2)
+            .enumerate()
+            .filter(|(_, pair)| pair[0].weight() == pair[1].weight())
+            .map(|(i, _)| i + 1)
+            .collect();
+
+        Ok(Progression { sessions, stalls })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use uom::num_rational::Rational64;
+    use uom::si::mass::kilogram;
+
+    use crate::test_support::{cm, kg};
+    use crate::{Bar, BarKind, Gym, GymError, Increment, LinearProgression, Plate, Rounding};
+
+    /// A 20kg bar with a pair of 5kg plates, so only 20kg and 30kg are achievable.
+    fn gym() -> Gym {
+        let gauge = cm(5);
+        let bar = Bar::new(kg(20), gauge, BarKind::Barbell);
+        let plates = vec![Plate::new(kg(5), gauge); 2];
+
+        Gym::new(&plates, &[bar])
+    }
+
+    fn progression() -> LinearProgression {
+        LinearProgression::new(kg(20), BarKind::Barbell, Increment::Fixed(kg((5, 2))), 6)
+    }
+
+    fn session_weights(gym: &Gym, progression: LinearProgression, rounding: Rounding) -> Vec<i64> {
+        gym.progression(progression, rounding)
+            .unwrap()
+            .sessions()
+            .iter()
+            .map(|session| session.weight().get::<kilogram>().to_integer())
+            .collect()
+    }
+
+    #[test]
+    fn targets_grow_by_the_increment() {
+        let relative = LinearProgression::new(
+            kg(100),
+            BarKind::Barbell,
+            Increment::Relative(Rational64::new(1, 10)),
+            3,
+        );
+
+        assert_eq!(
+            progression().targets().unwrap()[..3],
+            [40, 45, 50].map(|weight| kg((weight, 2)))
+        );
+        assert_eq!(relative.targets().unwrap(), [100, 110, 121].map(kg));
+    }
+
+    #[test]
+    fn compounding_past_what_fits_is_an_error() {
+        let progression = |sessions| {
+            LinearProgression::new(
+                kg(21),
+                BarKind::Barbell,
+                Increment::Relative(Rational64::new(1, 40)),
+                sessions,
+            )
+        };
+
+        assert!(progression(11).targets().is_ok());
+        assert!(matches!(
+            progression(12).targets(),
+            Err(GymError::ProgressionOverflow(12))
+        ));
+        assert!(matches!(
+            gym().progression(progression(12), Rounding::Down),
+            Err(GymError::ProgressionOverflow(_))
+        ));
+    }
+
+    #[test]
+    fn sessions_snap_to_achievable_weights() {
+        let gym = gym();
+
+        assert_eq!(
+            session_weights(&gym, progression(), Rounding::Down),
+            [20, 20, 20, 20, 30, 30]
+        );
+        assert_eq!(
+            session_weights(&gym, progression(), Rounding::Nearest),
+            [20, 20, 20, 30, 30, 30]
+        );
+
+        let progression = gym.progression(progression(), Rounding::Down).unwrap();
+        assert!(
+            progression
+                .sessions()
+                .iter()
+                .all(|session| session.bar_kind() == BarKind::Barbell)
+        );
+    }
+
+    #[test]
+    fn rounding_up_past_the_heaviest_weight_is_an_error() {
+        let gym = gym();
+        let short =
+            LinearProgression::new(kg(20), BarKind::Barbell, Increment::Fixed(kg((5, 2))), 5);
+
+        assert_eq!(
+            session_weights(&gym, short, Rounding::Up),
+            [20, 30, 30, 30, 30]
+        );
+        assert!(matches!(
+            gym.progression(progression(), Rounding::Up),
+            Err(GymError::ImpossibleRequirement(_))
+        ));
+    }
+
+    #[test]
+    fn stalls_are_sessions_that_repeat_the_last_weight() {
+        let progression = gym().progression(progression(), Rounding::Down).unwrap();
+
+        assert_eq!(progression.stalls(), [1, 2, 3, 5]);
+        assert!(progression.stalled());
+
+        let steady = LinearProgression::new(kg(20), BarKind::Barbell, Increment::Fixed(kg(10)), 2);
+        let progression = gym().progression(steady, Rounding::Down).unwrap();
+        assert!(progression.stalls().is_empty());
+        assert!(!progression.stalled());
+    }
+
+    #[test]
+    fn sessions_keep_the_quantity_of_implements() {
+        let gauge = cm(5);
+        let handle = Bar::new(kg(2), gauge, BarKind::Dumbbell);
+        let plates = vec![Plate::new(kg(5), gauge); 2];
+        let gym = Gym::new(&plates, &[handle]);
+
+        let single = LinearProgression::new(kg(2), BarKind::Dumbbell, Increment::Fixed(kg(10)), 2)
+            .with_quantity(1);
+        let progression = gym.progression(single, Rounding::Down).unwrap();
+
+        assert_eq!(progression.sessions()[1].weight(), kg(12));
+        assert!(
+            progression
+                .sessions()
+                .iter()
+                .all(|session| session.quantity() == 1)
+        );
+    }
+}
@@ -0,0 +1,214 @@
+use std::{
+    cmp::Reverse,
+    collections::{BTreeMap, BinaryHeap},
+    fmt::Display,
+};
+
+use derive_more::{From, IntoIterator};
+use itertools::Itertools;
+use uom::num_rational::Rational64;
+
+use crate::{Gym, GymError, Inventory, Limits, Plate, Requirement, format};
+
+/// The price of a single plate of each size that can be bought.
+#[derive(Clone, Debug, Default, IntoIterator, From)]
+#[into_iterator(owned, ref)]
+pub struct PriceList(pub BTreeMap<Plate, Rational64>);
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Purchase {
+    plates: Vec<Plate>,
+    cost: Rational64,
+    changes: u32,
+}
+
+impl Purchase {
+    #[must_use]
+    pub fn plates(&self) -> &[Plate] {
+        &self.plates
+    }
+
+    #[must_use]
+    pub fn cost(&self) -> Rational64 {
+        self.cost
+    }
+
+    /// The number of plate changes the requirements take once the plates are bought.
+    #[must_use]
+    pub fn changes(&self) -> u32 {
+        self.changes
+    }
+}
+
+impl Display for Purchase {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let plates = self
+            .plates
+            .iter()
+            .map(|p| p.weight())
+            .map(format::mass_to_dec_string)
+            .join(", ");
+
+        write!(
+            f,
+            "[{}] (cost {}, {} changes)",
+            plates, self.cost, self.changes
+        )
+    }
+}
+
+impl Gym {
+    ///
+    /// Finds the cheapest set of extra plates which makes every requirement achievable with
+    /// everything else in `inventory`. Plates are bought in pairs, one for each side of a bar,
+    /// and at most `max_pairs` pairs are considered. Purchases of equal cost are ranked by the
+    /// plate changes they need.
+    ///
+    /// # Errors
+    /// If no purchase of at most `max_pairs` pairs makes every requirement achievable, or the
+    /// inventory's weights overflow or it is too large to model.
+    ///
+    pub fn purchase(
+        inventory: &Inventory,
+        requirements: &[Requirement],
+        prices: &PriceList,
+        max_pairs: usize,
+    ) -> Result<Purchase, GymError> {
+        let gym = Gym::try_from_inventory(inventory, Limits::default())?;
+        let Some(unmet) = gym.unmet_requirement(requirements)? else {
+            return Ok(Purchase {
+                plates: Vec::new(),
+                cost: Rational64::default(),
+                changes: gym.changes(requirements)?,
+            });
+        };
+
+        let catalogue: Vec<(Plate, Rational64)> = prices
+            .into_iter()
+            .map(|(plate, price)| (*plate, *price * 2))
+            .collect();
+
+        let mut queue = BinaryHeap::new();
+        queue.push(Reverse((Rational64::default(), Vec::<usize>::new())));
+
+        let mut best: Option<Purchase> = None;
+
+        while let Some(Reverse((cost, picks))) = queue.pop() {
+            if best.as_ref().is_some_and(|best| cost > best.cost) {
+                break;
+            }
+
+            let extra: Vec<Plate> = picks.iter().flat_map(|i| [catalogue[*i].0; 2]).collect();
+            let mut bought = inventory.clone();
+            for plate in &extra {
+                bought.add_plate(*plate);
+            }
+            let gym = Gym::try_from_inventory(&bought, Limits::default())?;
+
+            if gym.unmet_requirement(requirements)?.is_none() {
+                let changes = gym.changes(requirements)?;
+
+                if best.as_ref().is_none_or(|best| changes < best.changes) {
+                    best = Some(Purchase {
+                        plates: extra,
+                        cost,
+                        changes,
+                    });
+                }
+
+                continue;
+            }
+
+            if picks.len() < max_pairs {
+                let start = picks.last().copied().unwrap_or_default();
+                for (i, (_, price)) in catalogue.iter().enumerate().skip(start) {
+                    let mut next = picks.clone();
+                    next.push(i);
+                    queue.push(Reverse((cost + price, next)));
+                }
+            }
+        }
+
+        best.ok_or(GymError::ImpossibleRequirement(unmet))
+    }
+
+    /// The first requirement no weight in the gym can meet, if any.
+    fn unmet_requirement(
+        &self,
+        requirements: &[Requirement],
+    ) -> Result<Option<Requirement>, GymError> {
+        for requirement in requirements {
+            if !self
+                .achievable(*requirement)?
+                .contains(&requirement.weight())
+            {
+                return Ok(Some(*requirement));
+            }
+        }
+
+        Ok(None)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::BTreeMap, str::FromStr};
+
+    use uom::num_rational::Rational64;
+
+    use crate::test_support::{cm, kg};
+    use crate::{Adapter, Bar, BarKind, Gym, Inventory, Plate, PriceList, Requirement};
+
+    fn plate(weight_kg: i64) -> Plate {
+        Plate::new(kg(weight_kg), cm(5))
+    }
+
+    #[test]
+    fn purchase_cheapest_plates_for_35b() {
+        let bar = Bar::new(kg(15), cm(5), BarKind::Barbell);
+
+        let prices = PriceList(BTreeMap::from([
+            (plate(5), Rational64::from_integer(10)),
+            (plate(10), Rational64::from_integer(15)),
+        ]));
+
+        let requirements = vec![
+            Requirement::from_str("25b").unwrap(),
+            Requirement::from_str("35b").unwrap(),
+        ];
+
+        let inventory = Inventory::new(vec![plate(5), plate(5)], vec![bar]);
+        let purchase =
+            Gym::purchase(&inventory, &requirements, &prices, 4).expect("purchase should succeed");
+
+        assert_eq!(purchase.plates(), &[plate(5), plate(5)]);
+        assert_eq!(purchase.cost(), Rational64::from_integer(20));
+    }
+
+    #[test]
+    fn purchase_nothing_when_adapters_already_fit_the_plates() {
+        let standard = cm((5, 2));
+        let bar = Bar::new(kg(10), standard, BarKind::Barbell);
+
+        let mut plates = vec![Plate::new(kg(5), standard); 2];
+        plates.extend(vec![plate(20); 2]);
+        let mut inventory = Inventory::new(plates, vec![bar]);
+        inventory.add_adapter(Adapter::new(standard, cm(5), kg(1)), 2);
+
+        let prices = PriceList(BTreeMap::from([(
+            Plate::new(kg(21), standard),
+            Rational64::from_integer(30),
+        )]));
+        let requirements = vec![
+            Requirement::from_str("20b").unwrap(),
+            Requirement::from_str("52b").unwrap(),
+        ];
+
+        let purchase =
+            Gym::purchase(&inventory, &requirements, &prices, 2).expect("purchase should succeed");
+
+        assert!(purchase.plates().is_empty());
+        assert_eq!(purchase.cost(), Rational64::default());
+        assert_eq!(purchase.changes(), 3);
+    }
+}
@@ -7,21 +7,67 @@ use uom::num_rational::Rational64;
 
 use crate::{BarKind, Dumbbell, GymError, format};
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct Requirement {
     weight: Mass,
     bar_kind: BarKind,
+    standard_height: bool,
+    quantity: usize,
 }
 
 impl Requirement {
+    pub(crate) const STANDARD_HEIGHT_SUFFIX: &str = "@floor";
+    pub(crate) const QUANTITY_SEPARATOR: char = 'x';
+
     #[must_use]
     pub fn new(weight: Mass, bar_kind: BarKind) -> Self {
-        Requirement { weight, bar_kind }
+        Requirement {
+            weight,
+            bar_kind,
+            standard_height: false,
+            quantity: bar_kind.default_quantity(),
+        }
+    }
+
+    /// The same requirement, for `quantity` identically loaded implements rather than the
+    /// kind's usual pair of dumbbells or single bar.
+    #[must_use]
+    pub fn with_quantity(self, quantity: usize) -> Self {
+        Requirement { quantity, ..self }
+    }
+
+    /// The same requirement at another weight.
+    #[must_use]
+    pub(crate) fn with_weight(self, weight: Mass) -> Self {
+        Requirement { weight, ..self }
+    }
+
+    /// The same requirement, lifted from the floor with a full-size plate setting the bar
+    /// height.
+    #[must_use]
+    pub fn at_standard_height(self) -> Self {
+        Requirement {
+            standard_height: true,
+            ..self
+        }
     }
 
     #[must_use]
     pub fn matches(self, dumbbell: &Dumbbell) -> bool {
-        self.weight == *dumbbell.weight() && self.bar_kind == *dumbbell.bar().kind()
+        self.weight == *dumbbell.weight()
+            && self.bar_kind == *dumbbell.bar().kind()
+            && (!self.standard_height || dumbbell.at_standard_height())
+    }
+
+    #[must_use]
+    pub fn standard_height(self) -> bool {
+        self.standard_height
+    }
+
+    /// How many implements are loaded to the weight at once.
+    #[must_use]
+    pub fn quantity(self) -> usize {
+        self.quantity
     }
 
     #[must_use]
@@ -53,12 +99,22 @@ impl Requirement {
 
 impl Display for Requirement {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.quantity != self.bar_kind.default_quantity() {
+            write!(f, "{}{}", self.quantity, Requirement::QUANTITY_SEPARATOR)?;
+        }
+
         write!(
             f,
             "{}kg {}",
             format::mass_to_dec_string(self.weight),
             self.bar_kind
-        )
+        )?;
+
+        if self.standard_height {
+            write!(f, " {}", Requirement::STANDARD_HEIGHT_SUFFIX)?;
+        }
+
+        Ok(())
     }
 }
 
@@ -66,15 +122,100 @@ impl FromStr for Requirement {
     type Err = GymError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Some(rest) = s
+            .trim_end()
+            .strip_suffix(Requirement::STANDARD_HEIGHT_SUFFIX)
+        {
+            return Requirement::from_str(rest).map(Requirement::at_standard_height);
+        }
+
+        if let Some((quantity, rest)) = s.trim_start().split_once(Requirement::QUANTITY_SEPARATOR)
+            && let Ok(quantity) = quantity.parse::<usize>()
+        {
+            if quantity == 0 {
+                return Err(GymError::InvalidRequirement(s.to_string()));
+            }
+            return Requirement::from_str(rest).map(|req| req.with_quantity(quantity));
+        }
+
         let parts = s
             .split_whitespace()
             .map(str::to_lowercase)
             .collect::<Vec<_>>();
 
         match (parts.len(), parts.as_slice()) {
-            (1, _) => Requirement::from_str_without_unit(s),
+            (1, _) => Requirement::from_str_without_unit(s.trim()),
             (3, [weight, unit, kind]) => Requirement::from_str_with_unit(weight, unit, kind),
             _ => Err(GymError::InvalidRequirement(s.to_string())),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use crate::test_support::{cm, kg};
+    use crate::{Bar, BarKind, Gym, GymError, Plate, Requirement};
+
+    #[test]
+    fn quantity_parses_and_defaults_to_the_kind() {
+        assert_eq!(
+            Requirement::from_str("2x7d").unwrap(),
+            Requirement::from_str("7d").unwrap()
+        );
+        assert_eq!(
+            Requirement::from_str("1x12d").unwrap().to_string(),
+            "1x12kg Dumbbell"
+        );
+        assert!(Requirement::from_str("0x12d").is_err());
+    }
+
+    #[test]
+    fn quantity_sets_how_many_implements_share_the_plates() {
+        let handle = Bar::new(kg(2), cm(3), BarKind::Dumbbell);
+
+        let mut plates = vec![Plate::new(kg(5), cm(3)); 2];
+        plates.extend(vec![Plate::new(kg((5, 2)), cm(3)); 4]);
+        let gym = Gym::new(&plates, &[handle]);
+        let workout =
+            |requirement: &str| gym.workout(&[Requirement::from_str(requirement).unwrap()]);
+
+        assert!(workout("12d").is_err());
+        let single = workout("1x12d").expect("one dumbbell can take both 5kg plates");
+        assert_eq!(single.get(handle)[0].plates(), [Plate::new(kg(5), cm(3))]);
+
+        assert!(workout("7d").is_ok());
+        assert!(workout("3x7d").is_err());
+        assert!(matches!(
+            workout("3x2d"),
+            Err(GymError::ImpossibleRequirement(_))
+        ));
+
+        let gym = Gym::new(&plates, &[handle, handle]);
+        assert!(
+            gym.workout(&[Requirement::from_str("3x2d").unwrap()])
+                .is_ok()
+        );
+    }
+
+    #[test]
+    fn quantities_of_a_kind_share_their_bars_in_requirement_order() {
+        let handle = Bar::new(kg(2), cm(3), BarKind::Dumbbell);
+
+        let mut plates = vec![Plate::new(kg(5), cm(3)); 4];
+        plates.extend(vec![Plate::new(kg((5, 2)), cm(3)); 2]);
+        let gym = Gym::new(&plates, &[handle]);
+        let requirements = ["12d", "1x17d", "12d"].map(|req| Requirement::from_str(req).unwrap());
+
+        let workout = gym.workout(&requirements).unwrap();
+        let weights: Vec<_> = workout
+            .get(handle)
+            .iter()
+            .map(|dumbbell| *dumbbell.weight())
+            .collect();
+
+        assert_eq!(weights, [12, 17, 12].map(kg));
+        assert_eq!(gym.changes(&requirements).unwrap(), 2);
+    }
+}
@@ -0,0 +1,11 @@
+use serde::{Deserialize, Serialize};
+use strum::Display;
+
+/// How a weight that can't be loaded exactly is moved onto an achievable one.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Display, Serialize, Deserialize)]
+pub enum Rounding {
+    Down,
+    Up,
+    /// The closest achievable weight, rounding down on a tie.
+    Nearest,
+}
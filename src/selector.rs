@@ -0,0 +1,180 @@
+use std::fmt::Display;
+
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
+use uom::si::rational64::{Length, Mass};
+
+use crate::{BarKind, Plate, format};
+
+/// A dial dumbbell or pin-loaded stack, set to one of a discrete list of weights. Moving the
+/// selection costs `change_cost` plate changes, and a stack with an add-on gauge also takes
+/// plates of that gauge on top of its setting.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct Selector {
+    kind: BarKind,
+    settings: Vec<Mass>,
+    change_cost: u32,
+    add_on_gauge: Option<Length>,
+}
+
+impl Selector {
+    #[must_use]
+    pub fn new(kind: BarKind, settings: Vec<Mass>, change_cost: u32) -> Self {
+        Selector {
+            kind,
+            settings: settings.into_iter().sorted().dedup().collect(),
+            change_cost,
+            add_on_gauge: None,
+        }
+    }
+
+    /// The same selector, taking add-on plates of `gauge`.
+    #[must_use]
+    pub fn with_add_ons(self, gauge: Length) -> Self {
+        Selector {
+            add_on_gauge: Some(gauge),
+            ..self
+        }
+    }
+
+    #[must_use]
+    pub fn kind(&self) -> &BarKind {
+        &self.kind
+    }
+
+    /// The selectable weights, lightest first.
+    #[must_use]
+    pub fn settings(&self) -> &[Mass] {
+        &self.settings
+    }
+
+    #[must_use]
+    pub fn change_cost(&self) -> u32 {
+        self.change_cost
+    }
+
+    #[must_use]
+    pub fn add_on_gauge(&self) -> Option<Length> {
+        self.add_on_gauge
+    }
+}
+
+impl Display for Selector {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let settings = self
+            .settings
+            .iter()
+            .map(|setting| format::mass_to_dec_string(*setting))
+            .join(", ");
+
+        write!(f, "{} selector [{}]", self.kind, settings)?;
+        if let Some(gauge) = self.add_on_gauge {
+            write!(f, " + {}cm add-ons", format::length_to_dec_string(gauge))?;
+        }
+
+        Ok(())
+    }
+}
+
+/// A selector set to one of its weights, with any add-on plates.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Selection {
+    selector: Selector,
+    setting: Mass,
+    plates: Vec<Plate>,
+    weight: Mass,
+}
+
+impl Selection {
+    /// The selector at `setting` with `plates` added on, which together weigh `weight`.
+    #[must_use]
+    pub(crate) fn with_weight(
+        selector: Selector,
+        setting: Mass,
+        plates: Vec<Plate>,
+        weight: Mass,
+    ) -> Self {
+        Selection {
+            selector,
+            setting,
+            plates,
+            weight,
+        }
+    }
+
+    #[must_use]
+    pub fn selector(&self) -> &Selector {
+        &self.selector
+    }
+
+    #[must_use]
+    pub fn setting(&self) -> Mass {
+        self.setting
+    }
+
+    /// The add-on plates, heaviest first.
+    #[must_use]
+    pub fn plates(&self) -> &[Plate] {
+        &self.plates
+    }
+
+    #[must_use]
+    pub fn weight(&self) -> &Mass {
+        &self.weight
+    }
+}
+
+impl Display for Selection {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let plates = self
+            .plates
+            .iter()
+            .map(|p| p.weight())
+            .map(format::mass_to_dec_string)
+            .join(", ");
+
+        write!(
+            f,
+            "{}kg + [{}] ({}kg)",
+            format::mass_to_dec_string(self.setting),
+            plates,
+            format::mass_to_dec_string(self.weight)
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use crate::test_support::{cm, kg, olympic_plates};
+    use crate::{BarKind, Gym, Implement, Inventory, Requirement, Selector};
+
+    #[test]
+    fn workout_moves_selector_pin_and_add_ons() {
+        let stack = Selector::new(BarKind::Machine, [10, 20, 30, 40].map(kg).to_vec(), 1)
+            .with_add_ons(cm(5));
+
+        let mut inventory = Inventory::new(olympic_plates((5, 2), 1), Vec::new());
+        inventory.add_selector(stack.clone());
+        let gym = Gym::from_inventory(&inventory);
+
+        let requirements = vec![
+            Requirement::from_str("30m").unwrap(),
+            Requirement::from_str("32.5m").unwrap(),
+            Requirement::from_str("40m").unwrap(),
+        ];
+
+        let workout = gym.workout(&requirements).expect("workout should succeed");
+        let selections = workout.selections(&stack);
+
+        assert_eq!(gym.changes(&requirements).unwrap(), 3);
+        assert_eq!(selections.len(), 3);
+        assert_eq!(selections[1].setting(), kg(30));
+        assert_eq!(selections[1].plates().len(), 1);
+        assert!(matches!(
+            workout.timeline()[2].implement(),
+            Implement::Selected(_)
+        ));
+    }
+}
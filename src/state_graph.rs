@@ -0,0 +1,653 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap, hash_map::Entry};
+
+use itertools::Itertools;
+use petgraph::{algo, prelude::UnGraphMap};
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+use uom::si::rational64::Mass;
+
+use crate::{
+    Bar, Dumbbell, GymError, GymState, GymStateRef, Handling, Implement, LoadingId, LoadingTable,
+    Machine, MachineLoading, MassScale, Plate, Requirement, Selection, Selector, Weighing,
+};
+
+/// The loadings each bar, selector and machine of one kind can take, with the plate changes
+/// between them. Each one is kept apart from the others, and plans combine them only as far as
+/// the requirements need.
+///
+/// Where loadings are otherwise equally good they are ranked by fewest plates, then lightest
+/// plates, then bar order, so the same input always produces the same plan.
+pub(crate) struct StateGraph {
+    scale: MassScale,
+    table: LoadingTable,
+    loads: Vec<Vec<Load>>,
+    distances: Vec<DistanceMatrix>,
+    index: HashMap<i64, Vec<(usize, LoadingId)>>,
+}
+
+/// A plan's state after a requirement, with its cost so far and the plan it continues.
+struct Plan {
+    state: GymState,
+    cost: u32,
+    previous: usize,
+}
+
+impl StateGraph {
+    /// Builds the graph for bars, selectors and machines, all of one kind, from each one's
+    /// loadings.
+    ///
+    /// # Errors
+    /// If a loading's weight or plates overflow the gym's mass scale.
+    ///
+    pub fn new(
+        dumbbells: BTreeMap<Bar, Vec<Dumbbell>>,
+        selections: BTreeMap<Selector, Vec<Selection>>,
+        machine_loadings: BTreeMap<Machine, Vec<MachineLoading>>,
+        scale: MassScale,
+        weighing: Weighing,
+    ) -> Result<Self, GymError> {
+        let table = LoadingTable::new(dumbbells, selections, machine_loadings);
+
+        #[cfg(feature = "parallel")]
+        let positions = (0..table.len()).into_par_iter();
+        #[cfg(not(feature = "parallel"))]
+        let positions = 0..table.len();
+
+        let loads = positions
+            .map(|position| {
+                table
+                    .dumbbells(position)
+                    .iter()
+                    .map(|dumbbell| Load::new(dumbbell, scale, weighing))
+                    .chain(
+                        table
+                            .selections(position)
+                            .iter()
+                            .map(|selection| Load::selected(selection, scale, weighing)),
+                    )
+                    .chain(
+                        table
+                            .machine_loadings(position)
+                            .iter()
+                            .map(|loading| Load::machine(loading, scale, weighing)),
+                    )
+                    .collect::<Result<Vec<Load>, GymError>>()
+            })
+            .collect::<Result<Vec<Vec<Load>>, GymError>>()?;
+
+        #[cfg(feature = "parallel")]
+        let positions = (0..table.len()).into_par_iter();
+        #[cfg(not(feature = "parallel"))]
+        let positions = 0..table.len();
+
+        let distances: Vec<DistanceMatrix> = positions
+            .map(|position| {
+                DistanceMatrix::new(loads[position].len(), Self::edges(&table, &loads, position))
+            })
+            .collect();
+
+        let mut graph = StateGraph {
+            scale,
+            table,
+            loads,
+            distances,
+            index: HashMap::new(),
+        };
+        graph.index = graph.index();
+        Ok(graph)
+    }
+
+    #[must_use]
+    pub fn selectors(&self) -> &[Selector] {
+        self.table.selectors()
+    }
+
+    #[must_use]
+    pub fn machines(&self) -> &[Machine] {
+        self.table.machines()
+    }
+
+    #[must_use]
+    pub fn state<'a>(&'a self, state: &'a GymState) -> GymStateRef<'a> {
+        GymStateRef::new(state, &self.table)
+    }
+
+    /// The implement `to` loads that `from` didn't have, if any. Each step of a plan loads at
+    /// most one.
+    #[must_use]
+    pub fn loaded(&self, from: Option<&GymState>, to: &GymState) -> Option<Implement> {
+        let (position, loading) = to
+            .loadings()
+            .iter()
+            .enumerate()
+            .filter_map(|(position, loading)| Some((position, (*loading)?)))
+            .find(|(position, loading)| {
+                from.and_then(|from| from.loadings()[*position]) != Some(*loading)
+            })?;
+
+        let dumbbells = self.table.dumbbells(position);
+        let selections = self.table.selections(position);
+        let machine_loadings = self.table.machine_loadings(position);
+        dumbbells
+            .get(loading.0)
+            .map(|dumbbell| Implement::Loaded(dumbbell.clone()))
+            .or_else(|| {
+                selections
+                    .get(loading.0)
+                    .map(|selection| Implement::Selected(selection.clone()))
+            })
+            .or_else(|| {
+                machine_loadings
+                    .get(loading.0)
+                    .map(|loading| Implement::Machine(loading.clone()))
+            })
+    }
+
+    /// The position and loading of `implement` in this graph, if it has them.
+    #[must_use]
+    pub fn locate(&self, implement: &Implement) -> Option<(usize, LoadingId)> {
+        let (position, loading) = match implement {
+            Implement::Loaded(dumbbell) => {
+                let position = self.table.position(dumbbell.bar())?;
+                let dumbbells = self.table.dumbbells(position);
+                (
+                    position,
+                    dumbbells.iter().position(|other| other == dumbbell)?,
+                )
+            }
+            Implement::Selected(selection) => {
+                let position = self.table.selector_position(selection.selector())?;
+                let selections = self.table.selections(position);
+                (
+                    position,
+                    selections.iter().position(|other| other == selection)?,
+                )
+            }
+            Implement::Machine(loading) => {
+                let position = self.table.machine_position(loading.machine())?;
+                let loadings = self.table.machine_loadings(position);
+                (
+                    position,
+                    loadings.iter().position(|other| other == loading)?,
+                )
+            }
+            Implement::Fixed(_) => return None,
+        };
+
+        Some((position, LoadingId(loading)))
+    }
+
+    /// The bars, selectors and machines that can be loaded to the requirement's weight, and
+    /// at standard height if it asks for that, each with its loading, best ranked first.
+    #[must_use]
+    pub fn candidates(&self, requirement: Requirement) -> Vec<(usize, LoadingId)> {
+        let Some(units) = self.scale.units(requirement.weight()) else {
+            return Vec::new();
+        };
+
+        self.index
+            .get(&units)
+            .into_iter()
+            .flatten()
+            .copied()
+            .filter(|(position, loading)| {
+                !requirement.standard_height() || self.loads[*position][loading.0].full_size
+            })
+            .collect()
+    }
+
+    /// The weights the bars, selectors and machines can be loaded to, counting only bars at
+    /// standard height if `standard_height`.
+    #[must_use]
+    pub fn weights(&self, standard_height: bool) -> BTreeSet<Mass> {
+        self.loads
+            .iter()
+            .flatten()
+            .filter(|load| !standard_height || load.full_size)
+            .map(|load| self.scale.mass(load.weight))
+            .collect()
+    }
+
+    /// The plate changes between two loadings at `position`. Loading a bar for the first time
+    /// is free, since it can be set up before the session.
+    #[must_use]
+    pub fn distance(&self, position: usize, from: Option<LoadingId>, to: LoadingId) -> u32 {
+        from.map_or(0, |from| self.distances[position].get(from, to))
+    }
+
+    /// Whether every plate in the loading at `position` is one the athlete can handle.
+    #[must_use]
+    pub fn handles(&self, position: usize, loading: LoadingId, handling: Handling) -> bool {
+        self.table
+            .stacks(position, loading)
+            .iter()
+            .flatten()
+            .all(|plate| handling.allows(plate))
+    }
+
+    /// The penalty for the plates put on and taken off between two loadings at `position`.
+    /// Each stack is stripped back to what the two loadings share before the new plates go on.
+    /// Loading a bar for the first time puts every one of its plates on.
+    #[must_use]
+    pub fn handling_penalty(
+        &self,
+        position: usize,
+        from: Option<LoadingId>,
+        to: LoadingId,
+        handling: Handling,
+    ) -> u32 {
+        if !handling.penalises() || from == Some(to) {
+            return 0;
+        }
+
+        let to = self.table.stacks(position, to);
+        let from = from.map_or_else(
+            || vec![Vec::new(); to.len()],
+            |from| self.table.stacks(position, from),
+        );
+
+        from.into_iter()
+            .zip(to)
+            .flat_map(|(from, to)| {
+                let shared = from.iter().zip(&to).take_while(|(a, b)| a == b).count();
+                from.into_iter()
+                    .skip(shared)
+                    .chain(to.into_iter().skip(shared))
+            })
+            .map(|plate| handling.penalty(&plate))
+            .fold(0, u32::saturating_add)
+    }
+
+    /// Orders loadings by fewest plates, then lightest plates.
+    #[must_use]
+    pub fn rank(&self, position: usize, loading: LoadingId) -> (usize, i64) {
+        let load = self.loads[position][loading.0];
+        (load.plates, load.plate_weight)
+    }
+
+    /// The states, one per requirement, with the fewest plate changes plus handling penalties,
+    /// where each requirement is met by one of its `candidates`. Each step loads one bar,
+    /// selector or machine and leaves the rest as they were.
+    ///
+    /// Plans that differ only in the loadings of bars no later requirement can use have the
+    /// same future, so only the cheapest of them is kept. Past `width` plans after a requirement
+    /// only the cheapest are carried on, so plans over many bars stay bounded but may miss the
+    /// fewest changes. Candidates are tried best ranked first and earlier plans win
+    /// ties, so ties go to fewest plates, then lightest plates, then bar order.
+    #[must_use]
+    pub fn plan(
+        &self,
+        candidates: &[Vec<(usize, LoadingId)>],
+        handling: Handling,
+        width: usize,
+    ) -> Vec<GymState> {
+        let mut used_later = vec![vec![false; self.table.len()]; candidates.len()];
+        for (step, later) in candidates.iter().enumerate().skip(1).rev() {
+            let mut used = used_later[step].clone();
+            for (position, _) in later {
+                used[*position] = true;
+            }
+            used_later[step - 1] = used;
+        }
+
+        let mut layers = vec![vec![Plan {
+            state: GymState::new(self.table.len()),
+            cost: 0,
+            previous: 0,
+        }]];
+
+        for (candidates, used_later) in candidates.iter().zip(&used_later) {
+            let previous_plans = &layers[layers.len() - 1];
+            let mut plans: Vec<Plan> = Vec::new();
+            let mut seen = HashMap::<GymState, usize>::new();
+
+            for (position, loading) in candidates {
+                for (previous, plan) in previous_plans.iter().enumerate() {
+                    let from = plan.state.loadings()[*position];
+                    let cost = plan
+                        .cost
+                        .saturating_add(self.distance(*position, from, *loading))
+                        .saturating_add(self.handling_penalty(*position, from, *loading, handling));
+                    let state = plan.state.with(*position, *loading);
+
+                    match seen.entry(state.keeping(used_later)) {
+                        Entry::Occupied(entry) => {
+                            let kept = &mut plans[*entry.get()];
+                            if cost < kept.cost {
+                                *kept = Plan {
+                                    state,
+                                    cost,
+                                    previous,
+                                };
+                            }
+                        }
+                        Entry::Vacant(entry) => {
+                            entry.insert(plans.len());
+                            plans.push(Plan {
+                                state,
+                                cost,
+                                previous,
+                            });
+                        }
+                    }
+                }
+            }
+
+            layers.push(Self::cheapest(plans, width.max(1)));
+        }
+
+        let Some(mut index) = layers[layers.len() - 1]
+            .iter()
+            .enumerate()
+            .min_by_key(|(_, plan)| plan.cost)
+            .map(|(index, _)| index)
+        else {
+            return Vec::new();
+        };
+
+        let mut sequence = Vec::with_capacity(candidates.len());
+        for plans in layers.iter().skip(1).rev() {
+            let plan = &plans[index];
+            sequence.push(plan.state.clone());
+            index = plan.previous;
+        }
+
+        sequence.reverse();
+        sequence
+    }
+
+    /// The `width` cheapest of `plans`, earlier plans first among equals, left in their order.
+    fn cheapest(plans: Vec<Plan>, width: usize) -> Vec<Plan> {
+        if plans.len() <= width {
+            return plans;
+        }
+
+        let mut kept = vec![false; plans.len()];
+        for (index, _) in plans
+            .iter()
+            .enumerate()
+            .sorted_by_key(|(index, plan)| (plan.cost, *index))
+            .take(width)
+        {
+            kept[index] = true;
+        }
+
+        plans
+            .into_iter()
+            .zip(kept)
+            .filter_map(|(plan, kept)| kept.then_some(plan))
+            .collect()
+    }
+
+    /// The single changes between loadings at `position`: one plate per side, one odd plate on
+    /// a single side, fitting or removing adapters, moving a selector with its add-on plates
+    /// left in place, or one plate on each horn of a pair.
+    fn edges(
+        table: &LoadingTable,
+        loads: &[Vec<Load>],
+        position: usize,
+    ) -> Vec<(usize, usize, u32)> {
+        let machine_loadings = table.machine_loadings(position);
+        if !machine_loadings.is_empty() {
+            return machine_loadings
+                .iter()
+                .enumerate()
+                .tuple_combinations()
+                .filter(|((_, loading1), (_, loading2))| {
+                    let steps: Vec<bool> = loading1
+                        .pairs()
+                        .zip(loading2.pairs())
+                        .filter(|(plates1, plates2)| plates1 != plates2)
+                        .map(|(plates1, plates2)| Self::one_plate_apart(plates1, plates2))
+                        .collect();
+                    steps == [true]
+                })
+                .map(|((i1, _), (i2, _))| (i1, i2, 1))
+                .collect();
+        }
+
+        let dumbbells = table.dumbbells(position);
+        let adapter = |i: usize| dumbbells.get(i).and_then(Dumbbell::adapter);
+
+        let adjacent = loads[position]
+            .iter()
+            .enumerate()
+            .tuple_combinations()
+            .filter(|((i1, load1), (i2, load2))| {
+                load1.adjacent(**load2) && adapter(*i1) == adapter(*i2)
+            })
+            .map(|((i1, _), (i2, _))| (i1, i2, 1));
+
+        let odd = dumbbells
+            .iter()
+            .enumerate()
+            .tuple_combinations()
+            .filter(|((_, dumbbell1), (_, dumbbell2))| Self::one_side_apart(dumbbell1, dumbbell2))
+            .map(|((i1, _), (i2, _))| (i1, i2, 1));
+
+        // Adapters go on and come off an empty bar, one change for each.
+        let fittings = dumbbells
+            .iter()
+            .enumerate()
+            .filter(|(_, dumbbell)| dumbbell.plates().is_empty())
+            .tuple_combinations()
+            .filter(|((_, dumbbell1), (_, dumbbell2))| dumbbell1.adapter() != dumbbell2.adapter())
+            .map(|((i1, dumbbell1), (i2, dumbbell2))| {
+                let changes = u32::from(dumbbell1.adapter().is_some())
+                    + u32::from(dumbbell2.adapter().is_some());
+                (i1, i2, changes)
+            });
+
+        let switches = table
+            .selections(position)
+            .iter()
+            .enumerate()
+            .tuple_combinations()
+            .filter(|((_, selection1), (_, selection2))| {
+                selection1.setting() != selection2.setting()
+                    && selection1.plates() == selection2.plates()
+            })
+            .map(|((i1, selection), (i2, _))| (i1, i2, selection.selector().change_cost()));
+
+        adjacent
+            .chain(odd)
+            .chain(fittings)
+            .chain(switches)
+            .collect()
+    }
+
+    /// Whether adding or removing one dumbbell's odd plate, or matching it on the other side,
+    /// gives the other, symmetric dumbbell.
+    fn one_side_apart(dumbbell1: &Dumbbell, dumbbell2: &Dumbbell) -> bool {
+        let (odd, even) = match (dumbbell1.odd_plate(), dumbbell2.odd_plate()) {
+            (Some(_), None) => (dumbbell1, dumbbell2),
+            (None, Some(_)) => (dumbbell2, dumbbell1),
+            _ => return false,
+        };
+
+        odd.adapter() == even.adapter() && odd.sides().iter().any(|side| side == even.plates())
+    }
+
+    /// Whether adding one plate to the outside of either stack gives the other.
+    fn one_plate_apart(plates1: &[Plate], plates2: &[Plate]) -> bool {
+        let (shorter, longer) = if plates1.len() < plates2.len() {
+            (plates1, plates2)
+        } else {
+            (plates2, plates1)
+        };
+
+        longer.len() == shorter.len() + 1 && longer.starts_with(shorter)
+    }
+
+    fn index(&self) -> HashMap<i64, Vec<(usize, LoadingId)>> {
+        let mut index = HashMap::<i64, Vec<(usize, LoadingId)>>::new();
+
+        for (position, loads) in self.loads.iter().enumerate() {
+            for (loading, load) in loads.iter().enumerate() {
+                index
+                    .entry(load.weight)
+                    .or_default()
+                    .push((position, LoadingId(loading)));
+            }
+        }
+
+        for candidates in index.values_mut() {
+            candidates.sort_by_key(|(position, loading)| {
+                (self.rank(*position, *loading), *position, *loading)
+            });
+        }
+
+        index
+    }
+}
+
+/// A loading reduced to the integer units the graph compares.
+#[derive(Clone, Copy)]
+struct Load {
+    plates: usize,
+    plate_weight: i64,
+    weight: i64,
+    last_plate: i64,
+    sides: i64,
+    setting: i64,
+    odd: bool,
+    full_size: bool,
+}
+
+impl Load {
+    fn new(dumbbell: &Dumbbell, scale: MassScale, weighing: Weighing) -> Result<Self, GymError> {
+        let overflow = || GymError::BarOverflow(*dumbbell.bar());
+        let [first, second] = dumbbell.sides();
+
+        Ok(Load {
+            plates: dumbbell.plates().len() + usize::from(dumbbell.odd_plate().is_some()),
+            plate_weight: Self::plate_units(first.iter().chain(&second), scale, weighing)
+                .ok_or_else(overflow)?,
+            weight: scale.units(*dumbbell.weight()).ok_or_else(overflow)?,
+            last_plate: dumbbell
+                .plates()
+                .last()
+                .map_or(Some(0), |plate| scale.units(plate.weighed(weighing)))
+                .ok_or_else(overflow)?,
+            sides: 2,
+            setting: 0,
+            odd: dumbbell.odd_plate().is_some(),
+            full_size: dumbbell.at_standard_height(),
+        })
+    }
+
+    /// A selection's add-on plates go on a single stack.
+    fn selected(
+        selection: &Selection,
+        scale: MassScale,
+        weighing: Weighing,
+    ) -> Result<Self, GymError> {
+        let overflow = || GymError::SelectorOverflow(selection.selector().clone());
+
+        Ok(Load {
+            plates: selection.plates().len(),
+            plate_weight: Self::plate_units(selection.plates(), scale, weighing)
+                .ok_or_else(overflow)?,
+            weight: scale.units(*selection.weight()).ok_or_else(overflow)?,
+            last_plate: selection
+                .plates()
+                .last()
+                .map_or(Some(0), |plate| scale.units(plate.weighed(weighing)))
+                .ok_or_else(overflow)?,
+            sides: 1,
+            setting: scale.units(selection.setting()).ok_or_else(overflow)?,
+            odd: false,
+            full_size: false,
+        })
+    }
+
+    /// A machine's loading, ranked by its resistance and the plates on one horn of each pair.
+    fn machine(
+        loading: &MachineLoading,
+        scale: MassScale,
+        weighing: Weighing,
+    ) -> Result<Self, GymError> {
+        let overflow = || GymError::MachineOverflow(*loading.machine());
+
+        Ok(Load {
+            plates: loading.pairs().map(<[Plate]>::len).sum(),
+            plate_weight: Self::plate_units(loading.pairs().flatten(), scale, weighing)
+                .and_then(|units| units.checked_mul(2))
+                .ok_or_else(overflow)?,
+            weight: scale.units(*loading.resistance()).ok_or_else(overflow)?,
+            last_plate: 0,
+            sides: 2,
+            setting: 0,
+            odd: false,
+            full_size: false,
+        })
+    }
+
+    /// The total weight of `plates`, unless it overflows.
+    fn plate_units<'a>(
+        plates: impl IntoIterator<Item = &'a Plate>,
+        scale: MassScale,
+        weighing: Weighing,
+    ) -> Option<i64> {
+        plates.into_iter().try_fold(0_i64, |acc, plate| {
+            acc.checked_add(scale.units(plate.weighed(weighing))?)
+        })
+    }
+
+    /// Whether one plate per side takes one load to the other without moving a selector. Loads
+    /// with an odd plate are joined separately.
+    fn adjacent(self, other: Self) -> bool {
+        if self.odd
+            || other.odd
+            || self.setting != other.setting
+            || self.plates.abs_diff(other.plates) != 1
+        {
+            return false;
+        }
+
+        let longer = if self.plates > other.plates {
+            self
+        } else {
+            other
+        };
+
+        longer
+            .last_plate
+            .checked_mul(longer.sides)
+            .and_then(|step| u64::try_from(step).ok())
+            == Some(self.weight.abs_diff(other.weight))
+    }
+}
+
+/// All-pairs plate changes between the loadings of a single bar, selector or machine.
+struct DistanceMatrix {
+    size: usize,
+    entries: Vec<u32>,
+}
+
+impl DistanceMatrix {
+    /// Shortest paths over `size` loadings joined by `edges` of the given cost.
+    fn new(size: usize, edges: Vec<(usize, usize, u32)>) -> Self {
+        let mut graph = UnGraphMap::<LoadingId, u32>::new();
+
+        for i in 0..size {
+            graph.add_node(LoadingId(i));
+        }
+
+        for (i1, i2, cost) in edges {
+            graph.add_edge(LoadingId(i1), LoadingId(i2), cost);
+        }
+
+        let mut entries = vec![u32::MAX; size * size];
+
+        for ((from, to), distance) in algo::johnson(&graph, |e| *e.2).unwrap_or_default() {
+            entries[from.0 * size + to.0] = distance;
+        }
+
+        DistanceMatrix { size, entries }
+    }
+
+    fn get(&self, from: LoadingId, to: LoadingId) -> u32 {
+        self.entries[from.0 * self.size + to.0]
+    }
+}
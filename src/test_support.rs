@@ -0,0 +1,34 @@
+use uom::num_rational::Rational64;
+use uom::si::{
+    length::centimeter,
+    mass::kilogram,
+    rational64::{Length, Mass},
+};
+
+use crate::Plate;
+
+/// A mass in kilograms, from a whole number or a `(numerator, denominator)` pair.
+pub(crate) fn kg(weight: impl Into<Rational64>) -> Mass {
+    Mass::new::<kilogram>(weight.into())
+}
+
+/// A length in centimetres, from a whole number or a `(numerator, denominator)` pair.
+pub(crate) fn cm(length: impl Into<Rational64>) -> Length {
+    Length::new::<centimeter>(length.into())
+}
+
+/// `count` 5cm plates of `weight` kilograms.
+pub(crate) fn olympic_plates(weight: impl Into<Rational64>, count: usize) -> Vec<Plate> {
+    vec![Plate::new(kg(weight), cm(5)); count]
+}
+
+/// A home gym's Olympic plates: twelve 2.5kg and a pair each of 5kg, 10kg, 15kg and 20kg.
+pub(crate) fn fixture_plates() -> Vec<Plate> {
+    let mut plates: Vec<Plate> = Vec::new();
+    plates.extend(olympic_plates((5, 2), 12));
+    plates.extend(olympic_plates(5, 2));
+    plates.extend(olympic_plates(10, 2));
+    plates.extend(olympic_plates(15, 2));
+    plates.extend(olympic_plates(20, 2));
+    plates
+}
@@ -0,0 +1,202 @@
+use derive_more::{From, IntoIterator};
+use itertools::Itertools;
+use uom::{num_rational::Rational64, si::rational64::Mass};
+
+use crate::{Gym, GymError, Requirement, Rounding, Weights};
+
+/// Warm-up sets as fractions of the working weight, in the order they are lifted.
+/// A fraction of zero stands for the empty bar.
+#[derive(Clone, Debug, PartialEq, Eq, IntoIterator, From)]
+#[into_iterator(owned, ref)]
+pub struct Ramp(pub Vec<Rational64>);
+
+impl Default for Ramp {
+    fn default() -> Self {
+        Ramp(vec![
+            Rational64::from_integer(0),
+            Rational64::new(2, 5),
+            Rational64::new(3, 5),
+            Rational64::new(4, 5),
+        ])
+    }
+}
+
+impl Gym {
+    ///
+    /// Warm-up requirements followed by the working set, ready for [`Gym::workout`].
+    /// Each warm-up is the achievable weight either side of its target, lighter than the working
+    /// set, chosen so the whole ramp needs the fewest plate changes and then stays closest to
+    /// the targets. Warm-ups are for as many implements as the working set, and at standard
+    /// height if it is.
+    ///
+    /// # Errors
+    /// If the working set or a warm-up can't be constructed given the user's plates.
+    ///
+    pub fn warm_up(&self, working: Requirement, ramp: &Ramp) -> Result<Vec<Requirement>, GymError> {
+        let weights = self.achievable(working)?;
+
+        let candidates = ramp
+            .into_iter()
+            .map(|fraction| {
+                let target = working.weight() * *fraction;
+                let candidates: Vec<Mass> = [Rounding::Down, Rounding::Up]
+                    .into_iter()
+                    .filter_map(|rounding| Weights::snap_in(&weights, target, rounding))
+                    .filter(|weight| *weight < working.weight())
+                    .dedup()
+                    .collect();
+
+                if candidates.is_empty() {
+                    Err(GymError::ImpossibleRequirement(working.with_weight(target)))
+                } else {
+                    Ok((target, candidates))
+                }
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mut best: Option<(u32, Mass, Vec<Requirement>)> = None;
+
+        for choice in candidates
+            .iter()
+            .map(|(_, candidates)| candidates.iter().copied())
+            .multi_cartesian_product()
+            .filter(|choice| choice.iter().tuple_windows().all(|(a, b)| a <= b))
+        {
+            let deviation = candidates
+                .iter()
+                .zip(&choice)
+                .map(|((target, _), weight)| (*weight - *target).abs())
+                .fold(Mass::default(), |acc, deviation| acc + deviation);
+
+            let requirements: Vec<Requirement> = choice
+                .into_iter()
+                .map(|weight| working.with_weight(weight))
+                .chain([working])
+                .collect();
+
+            let changes = self.changes(&requirements)?;
+
+            if best
+                .as_ref()
+                .is_none_or(|(best_changes, best_deviation, _)| {
+                    (changes, deviation) < (*best_changes, *best_deviation)
+                })
+            {
+                best = Some((changes, deviation, requirements));
+            }
+        }
+
+        best.map(|(_, _, requirements)| requirements)
+            .ok_or(GymError::ImpossibleRequirement(working))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use uom::num_rational::Rational64;
+    use uom::si::rational64::Mass;
+
+    use crate::test_support::{cm, kg};
+    use crate::{Bar, BarKind, Gym, GymError, Plate, Ramp, Requirement};
+
+    fn barbell_gym() -> Gym {
+        let gauge = cm(5);
+        let bar = Bar::new(kg(20), gauge, BarKind::Barbell);
+        let plates: Vec<Plate> = [20, 10, 5]
+            .into_iter()
+            .flat_map(|weight| [Plate::new(kg(weight), gauge); 2])
+            .collect();
+
+        Gym::new(&plates, &[bar])
+    }
+
+    fn weights(sets: &[Requirement]) -> Vec<Mass> {
+        sets.iter().map(|set| set.weight()).collect()
+    }
+
+    #[test]
+    fn warm_up_ramps_below_the_working_weight() {
+        let gym = barbell_gym();
+        let working = Requirement::from_str("60b").unwrap();
+
+        let sets = gym.warm_up(working, &Ramp::default()).unwrap();
+
+        assert_eq!(weights(&sets), [kg(20), kg(20), kg(40), kg(50), kg(60)]);
+        assert_eq!(sets.last(), Some(&working));
+    }
+
+    #[test]
+    fn warm_up_snaps_targets_to_achievable_weights() {
+        let gym = barbell_gym();
+        let working = Requirement::from_str("90b").unwrap();
+        let ramp = Ramp(vec![
+            Rational64::new(1, 4),
+            Rational64::new(1, 2),
+            Rational64::new(3, 4),
+        ]);
+
+        let sets = gym.warm_up(working, &ramp).unwrap();
+
+        // 67.5kg is nearer 70kg, but 60kg takes fewer plate changes from 40kg and on to 90kg.
+        assert_eq!(weights(&sets), [kg(20), kg(40), kg(60), kg(90)]);
+    }
+
+    #[test]
+    fn warm_up_empty_bar_is_the_bar_weight() {
+        let gym = barbell_gym();
+        let working = Requirement::from_str("40b").unwrap();
+        let ramp = Ramp(vec![Rational64::from_integer(0)]);
+
+        let sets = gym.warm_up(working, &ramp).unwrap();
+
+        assert_eq!(weights(&sets), [kg(20), kg(40)]);
+    }
+
+    #[test]
+    fn warm_up_without_a_lighter_weight_is_an_error() {
+        let gym = barbell_gym();
+        let working = Requirement::from_str("20b").unwrap();
+
+        assert!(matches!(
+            gym.warm_up(working, &Ramp::default()),
+            Err(GymError::ImpossibleRequirement(_))
+        ));
+    }
+
+    #[test]
+    fn warm_up_keeps_working_quantity_and_height() {
+        let gauge = cm(3);
+        let handle = Bar::new(kg(2), gauge, BarKind::Dumbbell);
+
+        let mut plates = vec![Plate::new(kg(5), gauge); 2];
+        plates.extend(vec![Plate::new(kg((5, 2)), gauge); 2]);
+        plates.extend(vec![Plate::new(kg((5, 4)), gauge); 4]);
+        let gym = Gym::new(&plates, &[handle]);
+
+        let working = Requirement::from_str("1x17d").unwrap();
+        let sets = gym.warm_up(working, &Ramp::default()).unwrap();
+
+        assert_eq!(
+            sets.iter().map(|set| set.weight()).collect::<Vec<_>>(),
+            [kg(2), kg(7), kg((19, 2)), kg(12), kg(17)]
+        );
+        assert!(sets.iter().all(|set| set.quantity() == 1));
+        assert!(gym.workout(&sets).is_ok());
+
+        let bar = Bar::new(kg(20), gauge, BarKind::Barbell);
+        let bumper = Plate::new(kg(10), gauge).with_diameter(Plate::standard_diameter());
+        let gym = Gym::new(
+            &[vec![Plate::new(kg(20), gauge); 2], vec![bumper; 2]].concat(),
+            &[bar],
+        );
+
+        let working = Requirement::from_str("80b @floor").unwrap();
+        let sets = gym.warm_up(working, &Ramp::default()).unwrap();
+
+        assert!(sets.iter().all(|set| set.standard_height()));
+        assert!(sets.iter().all(|set| set.weight() >= kg(40)));
+        assert!(gym.workout(&sets).is_ok());
+    }
+}
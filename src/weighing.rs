@@ -0,0 +1,66 @@
+use serde::{Deserialize, Serialize};
+use strum::Display;
+
+/// Which weight of each plate a gym plans with.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Display, Serialize, Deserialize)]
+pub enum Weighing {
+    /// The weight printed on the plate, which requirements are usually written against.
+    #[default]
+    Nominal,
+    /// The plate's measured weight, for lifters who want the exact load.
+    Actual,
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use uom::num_rational::Rational64;
+    use uom::si::{mass::kilogram, rational64::Mass};
+
+    use crate::test_support::{cm, kg};
+    use crate::{Bar, BarKind, Dumbbell, Gym, GymError, Inventory, Plate, Requirement, Weighing};
+
+    #[test]
+    fn workout_reports_actual_weights_and_can_plan_with_them() {
+        let bar = Bar::new(kg(20), cm(5), BarKind::Barbell);
+        let heavy = Plate::new(kg(20), cm(5)).with_actual(kg((41, 2)));
+
+        let mut inventory = Inventory::new(vec![heavy; 2], vec![bar]);
+        let plan = |inventory: &Inventory, requirement: &str| {
+            Gym::from_inventory(inventory)
+                .workout(&[Requirement::from_str(requirement).unwrap()])
+                .map(|workout| workout.get(bar)[0].clone())
+        };
+
+        let nominal = plan(&inventory, "60b").expect("nominal weights should meet 60b");
+        assert_eq!(*nominal.weight(), kg(60));
+        assert_eq!(nominal.actual_weight().unwrap(), kg(61));
+        assert!(nominal.to_string().ends_with("(60kg, actual 61kg)"));
+
+        inventory.set_weighing(Weighing::Actual);
+        assert!(plan(&inventory, "60b").is_err());
+        let actual = plan(&inventory, "61b").expect("actual weights should meet 61b");
+        assert_eq!(*actual.weight(), kg(61));
+        assert!(actual.to_string().ends_with("(61kg)"));
+    }
+
+    #[test]
+    fn actual_weight_reports_overflow() {
+        let bar = Bar::new(kg(20), cm(5), BarKind::Barbell);
+        let awkward = [
+            Rational64::new(1, 1 << 40),
+            Rational64::new(1, 3_i64.pow(30)),
+        ]
+        .map(|offset| {
+            Plate::new(kg(20), cm(5)).with_actual(kg(20) + Mass::new::<kilogram>(offset))
+        });
+        let dumbbell = Dumbbell::new(awkward.to_vec(), bar);
+
+        assert!(matches!(
+            dumbbell.actual_weight(),
+            Err(GymError::PlateOverflow(plate)) if plate == awkward[1]
+        ));
+        assert!(dumbbell.to_string().ends_with("(100kg)"));
+    }
+}
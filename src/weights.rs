@@ -1,17 +1,353 @@
-use std::collections::HashMap;
+use std::{
+    collections::{BTreeMap, BTreeSet, btree_map},
+    ops::RangeBounds,
+};
 
-use derive_more::{From, IntoIterator};
+use itertools::Itertools;
 use uom::si::rational64::Mass;
 
-use crate::BarKind;
+use crate::{
+    Bar, BarKind, Dumbbell, FixedWeight, Machine, MachineLoading, Rounding, Selection, Selector,
+};
 
-#[derive(Clone, IntoIterator, From)]
-#[into_iterator(owned, ref, ref_mut)]
-pub struct Weights(pub HashMap<BarKind, Vec<Mass>>);
+/// The weights achievable on each bar, selector and machine, along with the loadings that
+/// produce them. A machine's weights are its effective resistances.
+#[derive(Clone, Debug, Default)]
+pub struct Weights {
+    dumbbells: BTreeMap<Bar, Vec<Dumbbell>>,
+    selections: BTreeMap<Selector, Vec<Selection>>,
+    machine_loadings: BTreeMap<Machine, Vec<MachineLoading>>,
+    fixed: Vec<FixedWeight>,
+}
 
 impl Weights {
+    #[must_use]
+    pub(crate) fn new(
+        dumbbells: BTreeMap<Bar, Vec<Dumbbell>>,
+        selections: BTreeMap<Selector, Vec<Selection>>,
+        machine_loadings: BTreeMap<Machine, Vec<MachineLoading>>,
+        fixed: Vec<FixedWeight>,
+    ) -> Self {
+        Weights {
+            dumbbells,
+            selections,
+            machine_loadings,
+            fixed,
+        }
+    }
+
+    /// Distinct achievable weights for a bar kind, loaded, selected or fixed, lightest first.
+    /// For machines these are effective resistances.
     #[must_use]
     pub fn get(&self, kind: BarKind) -> Vec<Mass> {
-        self.0.get(&kind).cloned().unwrap_or_default()
+        self.kind_set(kind).into_iter().collect()
+    }
+
+    /// Distinct achievable weights for a single bar, lightest first.
+    #[must_use]
+    pub fn bar(&self, bar: Bar) -> Vec<Mass> {
+        self.dumbbells
+            .get(&bar)
+            .into_iter()
+            .flatten()
+            .map(|dumbbell| *dumbbell.weight())
+            .dedup()
+            .collect()
+    }
+
+    /// Distinct achievable weights for a single selector, lightest first.
+    #[must_use]
+    pub fn selector(&self, selector: &Selector) -> Vec<Mass> {
+        self.selections
+            .get(selector)
+            .into_iter()
+            .flatten()
+            .map(|selection| *selection.weight())
+            .dedup()
+            .collect()
+    }
+
+    /// Distinct effective resistances for a single machine, lightest first.
+    #[must_use]
+    pub fn machine(&self, machine: &Machine) -> Vec<Mass> {
+        self.machine_loadings
+            .get(machine)
+            .into_iter()
+            .flatten()
+            .map(|loading| *loading.resistance())
+            .dedup()
+            .collect()
+    }
+
+    /// Distinct achievable weights for every kind the gym has, as [`Weights::get`] lists them.
+    #[must_use]
+    pub fn by_kind(&self) -> BTreeMap<BarKind, Vec<Mass>> {
+        let kinds: BTreeSet<BarKind> = self
+            .dumbbells
+            .keys()
+            .map(|bar| *bar.kind())
+            .chain(self.selections.keys().map(|selector| *selector.kind()))
+            .chain(self.machine_loadings.keys().map(|machine| *machine.kind()))
+            .chain(self.fixed.iter().map(|fixed| *fixed.kind()))
+            .collect();
+
+        kinds
+            .into_iter()
+            .map(|kind| (kind, self.get(kind)))
+            .filter(|(_, weights)| !weights.is_empty())
+            .collect()
+    }
+
+    /// Each kind with its distinct achievable weights, in [`BarKind`] order.
+    #[must_use]
+    pub fn iter(&self) -> btree_map::IntoIter<BarKind, Vec<Mass>> {
+        self.by_kind().into_iter()
+    }
+
+    #[must_use]
+    pub fn bars(&self) -> Vec<Bar> {
+        self.dumbbells.keys().copied().collect()
+    }
+
+    /// The lightest achievable weight strictly above `weight`.
+    #[must_use]
+    pub fn next_up(&self, kind: BarKind, weight: Mass) -> Option<Mass> {
+        self.kind_set(kind)
+            .into_iter()
+            .find(|achievable| *achievable > weight)
+    }
+
+    /// The heaviest achievable weight strictly below `weight`.
+    #[must_use]
+    pub fn next_down(&self, kind: BarKind, weight: Mass) -> Option<Mass> {
+        self.kind_set(kind)
+            .into_iter()
+            .rev()
+            .find(|achievable| *achievable < weight)
+    }
+
+    /// The achievable weight `weight` rounds to, if any.
+    #[must_use]
+    pub fn snap(&self, kind: BarKind, weight: Mass, rounding: Rounding) -> Option<Mass> {
+        Self::snap_in(&self.kind_set(kind), weight, rounding)
+    }
+
+    /// The weight among `weights` that `weight` rounds to, if any.
+    pub(crate) fn snap_in(
+        weights: &BTreeSet<Mass>,
+        weight: Mass,
+        rounding: Rounding,
+    ) -> Option<Mass> {
+        let down = weights.range(..=weight).next_back().copied();
+        let up = weights.range(weight..).next().copied();
+
+        match (rounding, down, up) {
+            (Rounding::Down, down, _) => down,
+            (Rounding::Up, _, up) => up,
+            (Rounding::Nearest, Some(down), Some(up)) => Some(if up - weight < weight - down {
+                up
+            } else {
+                down
+            }),
+            (Rounding::Nearest, down, up) => down.or(up),
+        }
+    }
+
+    /// The smallest step between consecutive achievable weights within `range`.
+    #[must_use]
+    pub fn smallest_increment(&self, kind: BarKind, range: impl RangeBounds<Mass>) -> Option<Mass> {
+        self.kind_set(kind)
+            .range(range)
+            .tuple_windows()
+            .map(|(lighter, heavier)| *heavier - *lighter)
+            .min()
+    }
+
+    /// The consecutive pair of achievable weights furthest apart.
+    #[must_use]
+    pub fn largest_gap(&self, kind: BarKind) -> Option<(Mass, Mass)> {
+        self.kind_set(kind)
+            .into_iter()
+            .tuple_windows()
+            .max_by_key(|(lighter, heavier)| *heavier - *lighter)
+    }
+
+    /// Every loading, on any bar of the kind, that produces `weight`.
+    #[must_use]
+    pub fn loadings(&self, kind: BarKind, weight: Mass) -> Vec<&Dumbbell> {
+        self.dumbbells
+            .iter()
+            .filter(|(bar, _)| *bar.kind() == kind)
+            .flat_map(|(_, dumbbells)| dumbbells)
+            .filter(|dumbbell| *dumbbell.weight() == weight)
+            .collect()
+    }
+
+    pub(crate) fn kind_dumbbells(&self, kind: BarKind) -> BTreeMap<Bar, Vec<Dumbbell>> {
+        self.dumbbells
+            .iter()
+            .filter(|(bar, _)| *bar.kind() == kind)
+            .map(|(bar, dumbbells)| (*bar, dumbbells.clone()))
+            .collect()
+    }
+
+    pub(crate) fn kind_selections(&self, kind: BarKind) -> BTreeMap<Selector, Vec<Selection>> {
+        self.selections
+            .iter()
+            .filter(|(selector, _)| *selector.kind() == kind)
+            .map(|(selector, selections)| (selector.clone(), selections.clone()))
+            .collect()
+    }
+
+    pub(crate) fn kind_machine_loadings(
+        &self,
+        kind: BarKind,
+    ) -> BTreeMap<Machine, Vec<MachineLoading>> {
+        self.machine_loadings
+            .iter()
+            .filter(|(machine, _)| *machine.kind() == kind)
+            .map(|(machine, loadings)| (*machine, loadings.clone()))
+            .collect()
+    }
+
+    /// The fixed implements of the kind weighing exactly `weight`.
+    #[must_use]
+    pub fn fixed(&self, kind: BarKind, weight: Mass) -> Vec<FixedWeight> {
+        self.fixed
+            .iter()
+            .filter(|fixed| *fixed.kind() == kind && fixed.weight() == weight)
+            .copied()
+            .collect()
+    }
+
+    /// How many fixed implements of the kind weigh exactly `weight`.
+    pub(crate) fn fixed_count(&self, kind: BarKind, weight: Mass) -> usize {
+        self.fixed(kind, weight)
+            .iter()
+            .map(FixedWeight::count)
+            .sum()
+    }
+
+    fn kind_set(&self, kind: BarKind) -> BTreeSet<Mass> {
+        self.dumbbells
+            .iter()
+            .filter(|(bar, _)| *bar.kind() == kind)
+            .flat_map(|(_, dumbbells)| dumbbells)
+            .map(|dumbbell| *dumbbell.weight())
+            .chain(
+                self.selections
+                    .iter()
+                    .filter(|(selector, _)| *selector.kind() == kind)
+                    .flat_map(|(_, selections)| selections)
+                    .map(|selection| *selection.weight()),
+            )
+            .chain(
+                self.machine_loadings
+                    .iter()
+                    .filter(|(machine, _)| *machine.kind() == kind)
+                    .flat_map(|(_, loadings)| loadings)
+                    .map(|loading| *loading.resistance()),
+            )
+            .chain(
+                self.fixed
+                    .iter()
+                    .filter(|fixed| *fixed.kind() == kind)
+                    .map(FixedWeight::weight)
+                    .filter(|weight| self.fixed_count(kind, *weight) >= kind.default_quantity()),
+            )
+            .collect()
+    }
+}
+
+impl From<Weights> for BTreeMap<BarKind, Vec<Mass>> {
+    fn from(weights: Weights) -> Self {
+        weights.by_kind()
+    }
+}
+
+impl IntoIterator for Weights {
+    type Item = (BarKind, Vec<Mass>);
+    type IntoIter = btree_map::IntoIter<BarKind, Vec<Mass>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl IntoIterator for &Weights {
+    type Item = (BarKind, Vec<Mass>);
+    type IntoIter = btree_map::IntoIter<BarKind, Vec<Mass>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use crate::test_support::{cm, kg};
+    use crate::{Bar, BarKind, Gym, Plate};
+
+    #[test]
+    fn catalogue_for_barbell_with_small_plates() {
+        let gauge = cm(5);
+        let bar = Bar::new(kg(15), gauge, BarKind::Barbell);
+
+        let mut plates = vec![Plate::new(kg((5, 2)), gauge); 4];
+        plates.extend(vec![Plate::new(kg(5), gauge); 2]);
+
+        let weights = Gym::new(&plates, &[bar]).weights();
+        let twenty_two = kg(22);
+
+        assert_eq!(weights.get(BarKind::Barbell).len(), 5);
+        assert_eq!(weights.next_up(BarKind::Barbell, twenty_two), Some(kg(25)));
+        assert_eq!(
+            weights.next_down(BarKind::Barbell, twenty_two),
+            Some(kg(20))
+        );
+        assert_eq!(
+            weights.smallest_increment(BarKind::Barbell, ..),
+            Some(kg(5))
+        );
+        assert_eq!(weights.loadings(BarKind::Barbell, kg(25)).len(), 2);
+    }
+
+    #[test]
+    fn largest_gap_is_between_the_furthest_apart_weights() {
+        let gauge = cm(5);
+        let bar = Bar::new(kg(20), gauge, BarKind::Barbell);
+
+        let mut plates = vec![Plate::new(kg((5, 4)), gauge); 2];
+        plates.extend(vec![Plate::new(kg(10), gauge); 2]);
+
+        let weights = Gym::new(&plates, &[bar]).weights();
+
+        assert_eq!(
+            weights.largest_gap(BarKind::Barbell),
+            Some((kg((45, 2)), kg(40)))
+        );
+        assert_eq!(weights.largest_gap(BarKind::Dumbbell), None);
+    }
+
+    #[test]
+    fn weights_iterate_by_kind_in_kind_order() {
+        let gauge = cm(5);
+        let bar = Bar::new(kg(20), gauge, BarKind::Barbell);
+        let handle = Bar::new(kg(2), gauge, BarKind::Dumbbell);
+        let plates = vec![Plate::new(kg(10), gauge); 2];
+
+        let weights = Gym::new(&plates, &[bar, handle]).weights();
+        let expected = vec![
+            (BarKind::Dumbbell, vec![kg(2)]),
+            (BarKind::Barbell, vec![kg(20), kg(40)]),
+        ];
+
+        assert_eq!((&weights).into_iter().collect::<Vec<_>>(), expected);
+        assert_eq!(
+            BTreeMap::from(weights),
+            expected.into_iter().collect::<BTreeMap<_, _>>()
+        );
     }
 }
@@ -1,22 +1,121 @@
-use std::{collections::HashMap, fmt::Display};
+use std::{collections::BTreeMap, fmt::Display};
 
-use derive_more::{From, IntoIterator};
+use derive_more::IntoIterator;
 
-use crate::{bar::Bar, dumbbell::Dumbbell};
+use crate::{
+    FixedWeight, Machine, MachineLoading, Requirement, Selection, Selector, bar::Bar,
+    dumbbell::Dumbbell,
+};
 
-#[derive(Default, IntoIterator, From)]
-#[into_iterator(owned, ref, ref_mut)]
-pub struct Workout(pub HashMap<Bar, Vec<Dumbbell>>);
+/// What was used to meet a requirement.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Implement {
+    Loaded(Dumbbell),
+    Selected(Selection),
+    Machine(MachineLoading),
+    Fixed(FixedWeight),
+}
+
+impl Display for Implement {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Implement::Loaded(dumbbell) => write!(f, "{} {dumbbell}", dumbbell.bar()),
+            Implement::Selected(selection) => write!(f, "{} {selection}", selection.selector()),
+            Implement::Machine(loading) => write!(f, "{} {loading}", loading.machine()),
+            Implement::Fixed(fixed) => write!(f, "{fixed}"),
+        }
+    }
+}
+
+/// A requirement and the implement chosen for it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Step {
+    requirement: Requirement,
+    implement: Implement,
+}
+
+impl Step {
+    #[must_use]
+    pub fn new(requirement: Requirement, implement: Implement) -> Self {
+        Step {
+            requirement,
+            implement,
+        }
+    }
+
+    #[must_use]
+    pub fn requirement(&self) -> Requirement {
+        self.requirement
+    }
+
+    #[must_use]
+    pub fn implement(&self) -> &Implement {
+        &self.implement
+    }
+}
+
+#[derive(Default, IntoIterator)]
+pub struct Workout {
+    #[into_iterator(owned, ref, ref_mut)]
+    loadings: BTreeMap<Bar, Vec<Dumbbell>>,
+    selections: BTreeMap<Selector, Vec<Selection>>,
+    machine_loadings: BTreeMap<Machine, Vec<MachineLoading>>,
+    timeline: Vec<Step>,
+}
 
 impl Workout {
+    #[must_use]
+    pub fn new(
+        loadings: BTreeMap<Bar, Vec<Dumbbell>>,
+        selections: BTreeMap<Selector, Vec<Selection>>,
+        machine_loadings: BTreeMap<Machine, Vec<MachineLoading>>,
+        timeline: Vec<Step>,
+    ) -> Self {
+        Workout {
+            loadings,
+            selections,
+            machine_loadings,
+            timeline,
+        }
+    }
+
     #[must_use]
     pub fn bars(&self) -> Vec<Bar> {
-        self.0.keys().copied().collect()
+        self.loadings.keys().copied().collect()
     }
 
     #[must_use]
     pub fn get(&self, bar: Bar) -> Vec<Dumbbell> {
-        self.0.get(&bar).cloned().unwrap_or_default()
+        self.loadings.get(&bar).cloned().unwrap_or_default()
+    }
+
+    #[must_use]
+    pub fn selectors(&self) -> Vec<Selector> {
+        self.selections.keys().cloned().collect()
+    }
+
+    #[must_use]
+    pub fn selections(&self, selector: &Selector) -> Vec<Selection> {
+        self.selections.get(selector).cloned().unwrap_or_default()
+    }
+
+    #[must_use]
+    pub fn machines(&self) -> Vec<Machine> {
+        self.machine_loadings.keys().copied().collect()
+    }
+
+    #[must_use]
+    pub fn machine_loadings(&self, machine: &Machine) -> Vec<MachineLoading> {
+        self.machine_loadings
+            .get(machine)
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    /// Each requirement in the order it was given, with the implement used for it.
+    #[must_use]
+    pub fn timeline(&self) -> &[Step] {
+        &self.timeline
     }
 }
 
@@ -30,6 +129,36 @@ impl Display for Workout {
             }
         }
 
+        for (selector, selections) in &self.selections {
+            writeln!(f, "{selector}")?;
+            for selection in selections {
+                writeln!(f, "  - {selection}")?;
+            }
+        }
+
+        for (machine, loadings) in &self.machine_loadings {
+            writeln!(f, "{machine}")?;
+            for loading in loadings {
+                writeln!(f, "  - {loading}")?;
+            }
+        }
+
+        let fixed: Vec<&FixedWeight> = self
+            .timeline
+            .iter()
+            .filter_map(|step| match step.implement() {
+                Implement::Fixed(fixed) => Some(fixed),
+                Implement::Loaded(_) | Implement::Selected(_) | Implement::Machine(_) => None,
+            })
+            .collect();
+
+        if !fixed.is_empty() {
+            writeln!(f, "Fixed")?;
+            for fixed in fixed {
+                writeln!(f, "  - {fixed}")?;
+            }
+        }
+
         Ok(())
     }
 }